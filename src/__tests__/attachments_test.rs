@@ -0,0 +1,117 @@
+use super::fixtures::{target, temp_dir};
+use super::mock_server::{ok, MockServer};
+use crate::attachments::{download_all, find_urls, rewrite_links, Limits, ManifestEntry};
+use crate::backend::NativeBackend;
+use crate::gh::{fetch_context, FetchOptions, TargetType};
+use std::fs;
+
+#[test]
+fn test_find_urls_in_body_and_comments() {
+    let issue = serde_json::json!({
+        "number": 1,
+        "title": "Broken layout",
+        "body": "Looks like this:\n\
+                 ![screenshot](https://github.com/user-attachments/assets/1a2b \"shot\")\n\
+                 <img width=\"300\" src=\"https://user-images.githubusercontent.com/1/a.png\">\n\
+                 Logs: https://github.com/owner/repo/files/42/log.txt.\n\
+                 Docs at https://example.com/guide are not attachments.",
+        "html_url": "https://github.com/owner/repo/issues/1",
+        "user": {"login": "alice"},
+        "state": "open"
+    })
+    .to_string();
+    let comments = serde_json::json!([
+        {"user": {"login": "bob"}, "body": "Same here ![again](https://github.com/user-attachments/assets/1a2b)", "created_at": "2024-01-02T00:00:00Z"},
+        {"user": {"login": "carol"}, "body": "![chart](https://example.com/chart.png) <img src=\"http://169.254.169.254/a.png\"> ![badge](https://camo.githubusercontent.com/ab12)", "created_at": "2024-01-03T00:00:00Z"}
+    ])
+    .to_string();
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/1", ok(&issue)),
+        ("/repos/owner/repo/issues/1/comments?per_page=100", ok(&comments)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let target = target(TargetType::Issue, 1);
+
+    let context = fetch_context(&backend, &target, &FetchOptions::default()).unwrap();
+
+    assert_eq!(
+        find_urls(&context),
+        vec![
+            "https://github.com/user-attachments/assets/1a2b",
+            "https://user-images.githubusercontent.com/1/a.png",
+            "https://github.com/owner/repo/files/42/log.txt",
+            "https://camo.githubusercontent.com/ab12",
+        ]
+    );
+}
+
+#[test]
+fn test_download_all_saves_files_and_records_skips() {
+    let server = MockServer::start(vec![
+        ("/assets/shot", ok("small file")),
+        ("/big.log", ok(&"x".repeat(64))),
+        ("/second.txt", ok("also small")),
+    ]);
+    let urls: Vec<String> = ["/assets/shot", "/big.log", "/missing.png", "/second.txt"]
+        .iter()
+        .map(|path| format!("{}{}", server.url, path))
+        .collect();
+    let out_dir = temp_dir("attachments");
+    let limits = Limits {
+        max_file_bytes: 32,
+        max_total_bytes: 15,
+    };
+
+    let entries = download_all(&urls, &out_dir, &limits).unwrap();
+
+    assert_eq!(entries[0].path.as_deref(), Some("attachments/1-shot.txt"));
+    assert_eq!(entries[0].bytes, Some(10));
+    assert_eq!(
+        fs::read_to_string(out_dir.join("attachments/1-shot.txt")).unwrap(),
+        "small file"
+    );
+    assert_eq!(entries[1].skipped.as_deref(), Some("larger than 32 bytes"));
+    assert_eq!(entries[2].skipped.as_deref(), Some("HTTP status 404"));
+    assert_eq!(
+        entries[3].skipped.as_deref(),
+        Some("would exceed the total limit of 15 bytes")
+    );
+    assert!(entries[1..].iter().all(|e| e.path.is_none()));
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.join("attachments/manifest.json")).unwrap())
+            .unwrap();
+    assert_eq!(manifest.as_array().unwrap().len(), 4);
+    assert_eq!(manifest[0]["content_type"], "text/plain");
+    assert!(manifest[2].get("path").is_none());
+}
+
+#[test]
+fn test_rewrite_links_only_at_found_positions() {
+    let entry = |url: &str, path: Option<&str>| ManifestEntry {
+        url: url.to_string(),
+        path: path.map(str::to_string),
+        bytes: None,
+        content_type: None,
+        skipped: None,
+    };
+    let entries = [
+        entry("https://github.com/user-attachments/assets/a1", Some("attachments/1-a1.png")),
+        entry("https://camo.githubusercontent.com/ab12", Some("attachments/2-ab12.png")),
+        entry("https://github.com/user-attachments/assets/b2", None),
+    ];
+    let md = "![shot](https://github.com/user-attachments/assets/a1) and \
+              <img src=\"https://camo.githubusercontent.com/ab12\">\n\
+              Badge URL: `https://camo.githubusercontent.com/ab12`, \
+              longer: https://camo.githubusercontent.com/ab12cd, \
+              skipped: https://github.com/user-attachments/assets/b2";
+
+    assert_eq!(
+        rewrite_links(md, "github.com", &entries),
+        "![shot](attachments/1-a1.png) and \
+         <img src=\"attachments/2-ab12.png\">\n\
+         Badge URL: `https://camo.githubusercontent.com/ab12`, \
+         longer: https://camo.githubusercontent.com/ab12cd, \
+         skipped: https://github.com/user-attachments/assets/b2"
+    );
+}
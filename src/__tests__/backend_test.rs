@@ -0,0 +1,429 @@
+use super::fixtures::{commit_target, target};
+use super::mock_server::{ok, MockServer, Route};
+use crate::error::ErrorKind;
+use crate::backend::{api_url_for_host, next_page_url, Backend, NativeBackend};
+use crate::gh::{fetch_context, FetchOptions, TargetType};
+use crate::types::EventKind;
+
+const ISSUE: &str = r#"{
+    "number": 5,
+    "title": "Crash on start",
+    "body": "It crashes.",
+    "html_url": "https://github.com/owner/repo/issues/5",
+    "user": {"login": "alice"},
+    "state": "closed",
+    "locked": true,
+    "state_reason": "completed",
+    "labels": [{"name": "bug"}],
+    "assignees": [{"login": "bob"}],
+    "milestone": {"title": "v1.0"},
+    "created_at": "2024-01-01T00:00:00Z",
+    "updated_at": "2024-01-03T00:00:00Z",
+    "closed_at": "2024-01-03T00:00:00Z"
+}"#;
+
+const COMMENTS: &str = r#"[
+    {"user": {"login": "carol"}, "body": "Same here.", "created_at": "2024-01-02T00:00:00Z"},
+    {"user": null, "body": "Deleted user", "created_at": "2024-01-02T01:00:00Z"}
+]"#;
+
+const TIMELINE: &str = r#"[
+    {"event": "labeled", "actor": {"login": "bob"}, "created_at": "2024-01-01T01:00:00Z",
+     "label": {"name": "bug", "color": "d73a4a"}},
+    {"event": "cross-referenced", "actor": {"login": "carol"}, "created_at": "2024-01-02T00:00:00Z",
+     "source": {"type": "issue", "issue": {"number": 12, "title": "Follow-up",
+        "html_url": "https://github.com/owner/other/issues/12",
+        "repository": {"full_name": "owner/other"}}}},
+    {"event": "subscribed", "actor": {"login": "dave"}, "created_at": "2024-01-02T01:00:00Z"},
+    {"event": "locked", "actor": {"login": "bob"}, "created_at": "2024-01-03T00:00:00Z",
+     "lock_reason": "resolved"}
+]"#;
+
+#[test]
+fn test_next_page_url() {
+    let link = r#"<https://api.github.com/x?page=2>; rel="next", <https://api.github.com/x?page=9>; rel="last""#;
+    assert_eq!(
+        next_page_url(link).as_deref(),
+        Some("https://api.github.com/x?page=2")
+    );
+    assert_eq!(
+        next_page_url(r#"<https://api.github.com/x?page=1>; rel="prev""#),
+        None
+    );
+}
+
+#[test]
+fn test_api_url_for_host() {
+    assert_eq!(api_url_for_host("github.com"), "https://api.github.com");
+    assert_eq!(
+        api_url_for_host("ghe.example.com"),
+        "https://ghe.example.com/api/v3"
+    );
+}
+
+#[test]
+fn test_native_enterprise_graphql_endpoint() {
+    let server = MockServer::start(vec![("/api/graphql", ok(r#"{"data": {"ok": true}}"#))]);
+    let backend = NativeBackend::new(&format!("{}/api/v3", server.url), "secret");
+
+    let response = backend.graphql("query { ok }", &[]).unwrap();
+    assert_eq!(response["data"]["ok"], true);
+    assert_eq!(server.paths(), vec!["/api/graphql"]);
+}
+
+#[test]
+fn test_native_rest_pages_follows_link_header() {
+    let server = MockServer::start(vec![
+        (
+            "/items",
+            Route {
+                status: 200,
+                body: "[1, 2]".to_string(),
+                link: Some("<{url}/items?page=2>; rel=\"next\"".to_string()),
+                etag: None,
+                retry_after: None,
+            },
+        ),
+        ("/items?page=2", ok("[3]")),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let pages = backend.rest_pages("items").unwrap();
+    assert_eq!(pages, vec![serde_json::json!([1, 2]), serde_json::json!([3])]);
+
+    let requests = server.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests
+        .iter()
+        .all(|r| r.authorization.as_deref() == Some("Bearer secret")));
+}
+
+#[test]
+fn test_native_reports_api_error_message() {
+    let server = MockServer::start(vec![(
+        "/repos/owner/repo/issues/5",
+        Route {
+            status: 403,
+            body: r#"{"message":"Resource not accessible by integration"}"#.to_string(),
+            link: None,
+            etag: None,
+            retry_after: None,
+        },
+    )]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let err = backend.view(&target(TargetType::Issue, 5)).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("403"));
+    assert!(message.contains("Resource not accessible by integration"));
+}
+
+#[test]
+fn test_native_list_issue_numbers_skips_pull_requests() {
+    let server = MockServer::start(vec![
+        (
+            "/repos/owner/repo/issues?state=open&per_page=3&page=1",
+            ok(r#"[{"number": 9}, {"number": 8, "pull_request": {}}, {"number": 7}]"#),
+        ),
+        (
+            "/repos/owner/repo/issues?state=open&per_page=3&page=2",
+            ok(r#"[{"number": 6}, {"number": 5}]"#),
+        ),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let numbers = backend.list_issue_numbers("owner/repo", "open", 3).unwrap();
+    assert_eq!(numbers, vec![9, 7, 6]);
+}
+
+#[test]
+fn test_fetch_context_with_native_backend() {
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/5", ok(ISSUE)),
+        ("/repos/owner/repo/issues/5/comments?per_page=100", ok(COMMENTS)),
+        ("/repos/owner/repo/issues/5/timeline?per_page=100", ok(TIMELINE)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(
+        &backend,
+        &target(TargetType::Issue, 5),
+        &FetchOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(context.title, "Crash on start");
+    assert_eq!(context.metadata.repo, "owner/repo");
+    assert_eq!(context.metadata.author, "alice");
+    assert_eq!(context.metadata.state.as_deref(), Some("CLOSED"));
+    assert_eq!(context.metadata.state_reason.as_deref(), Some("COMPLETED"));
+    assert_eq!(context.metadata.labels, vec!["bug"]);
+    assert_eq!(context.metadata.assignees, vec!["bob"]);
+    assert_eq!(context.metadata.milestone.as_deref(), Some("v1.0"));
+    assert!(context.metadata.locked);
+    assert_eq!(context.comments.len(), 2);
+    assert_eq!(context.comments[1].author, "ghost");
+    assert_eq!(context.events.len(), 4);
+    assert!(matches!(&context.events[0].kind, EventKind::Labeled { label } if label == "bug"));
+    match &context.events[1].kind {
+        EventKind::CrossReferenced { source } => {
+            assert_eq!(source.repo, "owner/other");
+            assert_eq!(source.number, 12);
+        }
+        other => panic!("expected a cross-reference, got {:?}", other),
+    }
+    assert!(matches!(&context.events[2].kind, EventKind::Other { event } if event == "subscribed"));
+    assert_eq!(context.events[3].actor, "bob");
+    assert!(context.commits.is_none());
+    assert!(server.paths().iter().all(|p| !p.contains("/pulls/")));
+}
+
+#[test]
+fn test_locked_comes_from_the_issue_payload() {
+    // Unlocked since, with the lock event still in the timeline.
+    let issue = ISSUE.replace(r#""locked": true"#, r#""locked": false"#);
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/5", ok(&issue)),
+        ("/repos/owner/repo/issues/5/comments?per_page=100", ok(COMMENTS)),
+        ("/repos/owner/repo/issues/5/timeline?per_page=100", ok(TIMELINE)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(
+        &backend,
+        &target(TargetType::Issue, 5),
+        &FetchOptions::default(),
+    )
+    .unwrap();
+
+    assert!(!context.metadata.locked);
+}
+
+#[test]
+fn test_comment_commit_and_review_events_keep_actor_and_time() {
+    let timeline = r#"[
+        {"event": "committed", "sha": "0123456789abcdef", "message": "Fix crash",
+         "author": {"name": "Alice A", "email": "a@example.com", "date": "2024-01-01T12:00:00Z"},
+         "committer": {"name": "GitHub", "email": "noreply@github.com", "date": "2024-01-01T13:00:00Z"}},
+        {"event": "commented", "actor": {"login": "carol"}, "user": {"login": "carol"},
+         "created_at": "2024-01-02T00:00:00Z", "body": "Same here."},
+        {"event": "reviewed", "user": {"login": "dave"}, "state": "approved",
+         "submitted_at": "2024-01-02T01:00:00Z", "body": null}
+    ]"#;
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/5", ok(ISSUE)),
+        ("/repos/owner/repo/issues/5/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/5/timeline?per_page=100", ok(timeline)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(
+        &backend,
+        &target(TargetType::Issue, 5),
+        &FetchOptions::default(),
+    )
+    .unwrap();
+
+    let events = &context.events;
+    assert_eq!(events.len(), 3);
+    assert!(matches!(&events[0].kind, EventKind::Committed { commit: Some(sha) } if sha == "0123456789abcdef"));
+    assert_eq!(events[0].actor, "Alice A");
+    assert_eq!(events[0].created_at.as_deref(), Some("2024-01-01T13:00:00Z"));
+    assert!(matches!(&events[1].kind, EventKind::Commented));
+    assert_eq!(events[1].actor, "carol");
+    assert!(matches!(&events[2].kind, EventKind::Reviewed { state: Some(state) } if state == "approved"));
+    assert_eq!(events[2].actor, "dave");
+    assert_eq!(events[2].created_at.as_deref(), Some("2024-01-02T01:00:00Z"));
+}
+
+#[test]
+fn test_fetch_context_pr_with_native_backend() {
+    let pull = r#"{
+        "draft": true,
+        "merged_at": null,
+        "base": {"ref": "main", "sha": "aaa"},
+        "head": {"ref": "feature", "sha": "bbb"}
+    }"#;
+    let reviews = r#"[
+        {"user": {"login": "dave"}, "state": "APPROVED", "submitted_at": "2024-01-02T00:00:00Z", "body": ""},
+        {"user": {"login": "erin"}, "state": "PENDING", "submitted_at": null, "body": null}
+    ]"#;
+    let commits = r#"[{
+        "sha": "bbb",
+        "author": null,
+        "commit": {
+            "message": "Fix crash\n\nGuard against a missing config.",
+            "author": {"name": "Alice A", "date": "2024-01-01T12:00:00Z"}
+        }
+    }]"#;
+    let threads = r#"{"data": {"repository": {"pullRequest": {"reviewThreads": {
+        "pageInfo": {"hasNextPage": false, "endCursor": null},
+        "nodes": [{
+            "path": "src/main.rs", "line": 3, "startLine": null,
+            "originalLine": 3, "originalStartLine": null,
+            "isResolved": true, "isOutdated": false,
+            "comments": {"nodes": [{"author": {"login": "dave"}, "body": "nit",
+                "createdAt": "2024-01-02T00:00:00Z", "diffHunk": "@@ -1 +1 @@"}]}
+        }]
+    }}}}}"#;
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/5", ok(ISSUE)),
+        ("/repos/owner/repo/issues/5/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/5/timeline?per_page=100", ok("[]")),
+        ("/repos/owner/repo/pulls/5", ok(pull)),
+        ("/repos/owner/repo/pulls/5/reviews?per_page=100", ok(reviews)),
+        ("/repos/owner/repo/pulls/5/commits?per_page=100", ok(commits)),
+        ("/graphql", ok(threads)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(
+        &backend,
+        &target(TargetType::Pr, 5),
+        &FetchOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(context.metadata.r#type, "pr");
+    assert_eq!(context.metadata.draft, Some(true));
+    assert_eq!(context.metadata.state_reason, None);
+    assert_eq!(context.metadata.base_branch.as_deref(), Some("main"));
+    assert_eq!(context.metadata.head_branch.as_deref(), Some("feature"));
+
+    let reviews = context.reviews.unwrap();
+    assert_eq!(reviews.len(), 1);
+    assert_eq!(reviews[0].author, "dave");
+
+    let commits = context.commits.unwrap();
+    assert_eq!(commits[0].authors, vec!["Alice A"]);
+    assert_eq!(commits[0].headline, "Fix crash");
+    assert_eq!(
+        commits[0].message,
+        "Fix crash\n\nGuard against a missing config."
+    );
+
+    let threads = context.review_threads.unwrap();
+    assert_eq!(threads.len(), 1);
+    assert_eq!(threads[0].path, "src/main.rs");
+    assert!(threads[0].is_resolved);
+    assert_eq!(threads[0].diff_hunk, "@@ -1 +1 @@");
+}
+
+#[test]
+fn test_fetch_context_commit_with_native_backend() {
+    let commit = r#"{
+        "sha": "abc1234def5678",
+        "html_url": "https://github.com/owner/repo/commit/abc1234def5678",
+        "author": null,
+        "commit": {
+            "message": "Fix crash on start\n\nFixes #5.",
+            "author": {"name": "Alice A", "date": "2024-01-01T12:00:00Z"}
+        }
+    }"#;
+    let comments = r#"[
+        {"user": {"login": "bob"}, "body": "Nice.", "created_at": "2024-01-02T00:00:00Z",
+         "path": "src/main.rs", "line": 3}
+    ]"#;
+    let pulls = r#"[{"number": 9, "title": "Fix crash", "html_url": "https://github.com/owner/repo/pull/9",
+        "state": "closed", "merged_at": "2024-01-03T00:00:00Z"}]"#;
+    let diff = "diff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-a\n+b\n";
+    // The commit and its diff share a path: JSON is requested first, then the diff.
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/commits/abc1234", ok(commit)),
+        ("/repos/owner/repo/commits/abc1234", ok(diff)),
+        ("/repos/owner/repo/commits/abc1234/comments?per_page=100", ok(comments)),
+        ("/repos/owner/repo/commits/abc1234/pulls?per_page=100", ok(pulls)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let target = commit_target("abc1234");
+
+    let context = fetch_context(&backend, &target, &FetchOptions::default()).unwrap();
+
+    assert_eq!(context.metadata.r#type, "commit");
+    assert_eq!(context.metadata.number, None);
+    assert_eq!(context.metadata.sha.as_deref(), Some("abc1234def5678"));
+    assert_eq!(context.metadata.author, "Alice A");
+    assert_eq!(context.title, "Fix crash on start");
+    assert_eq!(context.body, "Fixes #5.");
+    assert_eq!(context.comments[0].body, "_On `src/main.rs` line 3_\n\nNice.");
+    let pulls = context.pull_requests.unwrap();
+    assert_eq!(pulls[0].number, 9);
+    assert_eq!(pulls[0].state, "merged");
+    let diff = context.diff.unwrap();
+    assert_eq!(diff.len(), 1);
+    assert_eq!(diff[0].path, "src/main.rs");
+}
+
+#[test]
+fn test_job_log_failures() {
+    let commit = r#"{
+        "sha": "abc1234def5678",
+        "html_url": "https://github.com/owner/repo/commit/abc1234def5678",
+        "author": null,
+        "commit": {"message": "Fix crash", "author": {"name": "Alice A", "date": null}}
+    }"#;
+    let check_runs = r#"{"check_runs": [
+        {"id": 1, "name": "expired", "status": "completed", "conclusion": "failure",
+         "html_url": null, "app": {"slug": "github-actions"}, "output": {"annotations_count": 0}},
+        {"id": 2, "name": "broken", "status": "completed", "conclusion": "failure",
+         "html_url": null, "app": {"slug": "github-actions"}, "output": {"annotations_count": 0}},
+        {"id": 3, "name": "test", "status": "completed", "conclusion": "failure",
+         "html_url": null, "app": {"slug": "github-actions"}, "output": {"annotations_count": 0}}
+    ]}"#;
+    let failing = |status: u16| Route {
+        status,
+        body: r#"{"message":"failed"}"#.to_string(),
+        link: None,
+        etag: None,
+        retry_after: None,
+    };
+    let routes = |job_3: Route| {
+        vec![
+            ("/repos/owner/repo/commits/abc1234", ok(commit)),
+            ("/repos/owner/repo/commits/abc1234/comments?per_page=100", ok("[]")),
+            ("/repos/owner/repo/commits/abc1234/pulls?per_page=100", ok("[]")),
+            ("/repos/owner/repo/commits/abc1234def5678/check-runs?per_page=100", ok(check_runs)),
+            ("/repos/owner/repo/commits/abc1234def5678/status", ok(r#"{"statuses": []}"#)),
+            ("/repos/owner/repo/actions/jobs/2/logs", failing(500)),
+            ("/repos/owner/repo/actions/jobs/3/logs", job_3),
+        ]
+    };
+    let target = commit_target("abc1234");
+    let options = FetchOptions::builder().checks(true).job_logs(1000).build().unwrap();
+
+    // A missing or broken log only drops that excerpt.
+    let server = MockServer::start(routes(ok("##[error]Process completed with exit code 1.")));
+    let backend = NativeBackend::new(&server.url, "secret");
+    let context = fetch_context(&backend, &target, &options).unwrap();
+    let runs = context.checks.unwrap().check_runs;
+    assert_eq!(runs[0].log_excerpt, None);
+    assert_eq!(runs[1].log_excerpt, None);
+    assert!(runs[2].log_excerpt.as_deref().unwrap().contains("exit code 1"));
+
+    // Auth failures are not hidden behind a missing excerpt.
+    let server = MockServer::start(routes(failing(401)));
+    let backend = NativeBackend::new(&server.url, "secret");
+    let err = fetch_context(&backend, &target, &options).unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::AuthRequired);
+}
+
+#[test]
+fn test_timeline_failures_fail_the_context() {
+    let forbidden = Route {
+        status: 403,
+        body: r#"{"message":"Resource not accessible by integration"}"#.to_string(),
+        link: None,
+        etag: None,
+        retry_after: None,
+    };
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/5", ok(ISSUE)),
+        ("/repos/owner/repo/issues/5/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/5/timeline?per_page=100", forbidden),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let err = fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default())
+        .unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::Forbidden);
+}
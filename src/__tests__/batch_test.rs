@@ -0,0 +1,64 @@
+use super::fixtures::temp_dir;
+use super::mock_server::{ok, MockServer};
+use crate::backend::NativeBackend;
+use crate::batch::Batch;
+use crate::format::Format;
+use crate::gh::{fetch_context, FetchOptions, TargetType};
+use std::fs;
+use std::process::ExitCode;
+
+#[test]
+fn test_batch_writes_files_in_order_and_collects_failures() {
+    let issue = |number: u64| {
+        serde_json::json!({
+            "number": number,
+            "title": format!("Issue {}", number),
+            "body": "",
+            "html_url": format!("https://github.com/owner/repo/issues/{}", number),
+            "user": {"login": "alice"},
+            "state": "open"
+        })
+        .to_string()
+    };
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/1", ok(&issue(1))),
+        ("/repos/owner/repo/issues/1/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/3", ok(&issue(3))),
+        ("/repos/owner/repo/issues/3/comments?per_page=100", ok("[]")),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let out_dir = temp_dir("batch");
+    let batch = Batch {
+        host: "github.com",
+        owner: "owner",
+        repo: "repo",
+        kind: TargetType::Issue,
+        out_dir: &out_dir,
+        format: Format::Json,
+        jobs: 2,
+    };
+
+    let mut recorded = Vec::new();
+    let fetch = |target: &_| fetch_context(&backend, target, &FetchOptions::default());
+    let failures = batch
+        .run(&[1, 2, 3], |&number| number, fetch, |&number, result| {
+            recorded.push((number, result.map(str::to_string).map_err(|f| f.number)));
+            Ok(())
+        })
+        .unwrap();
+
+    assert_eq!(
+        recorded,
+        vec![
+            (1, Ok("repo-issue-1.json".to_string())),
+            (2, Err(2)),
+            (3, Ok("repo-issue-3.json".to_string())),
+        ]
+    );
+    let written: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.join("repo-issue-3.json")).unwrap()).unwrap();
+    assert_eq!(written["title"], "Issue 3");
+
+    assert_eq!(batch.finish("issue", "for owner/repo", 2, &failures).unwrap(), ExitCode::from(3));
+    assert!(out_dir.join("failures.json").is_file());
+}
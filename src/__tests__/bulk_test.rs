@@ -1,7 +1,10 @@
-use crate::args::{Cli, IssueState, OutputFormat};
+use crate::args::{BackendKind, Cli, IssueState, Layout, OutputFormat};
 use crate::bulk::{
     resolve_bulk_out_dir, resolve_pr_range_out_dir, validate_bulk_args, validate_pr_range_args,
+    validate_jobs_args, validate_resume_args, validate_sync_args,
 };
+use crate::{fetch_options, run};
+use gh_context::ErrorKind;
 use std::fs;
 use std::path::PathBuf;
 
@@ -9,16 +12,43 @@ fn make_cli() -> Cli {
     Cli {
         input: "owner/repo".to_string(),
         format: OutputFormat::Md,
+        layout: Layout::Sections,
+        backend: BackendKind::Gh,
+        hostname: None,
+        graphql: false,
         out: None,
         clip: false,
         issue: false,
         pr: false,
         bulk: true,
         state: IssueState::Open,
-        per_page: 30,
-        pages: 1,
+        per_page: None,
+        pages: None,
+        sync: false,
+        sync_full: false,
+        resume: false,
         from: None,
         to: None,
+        diff: false,
+        diff_include: Vec::new(),
+        diff_exclude: Vec::new(),
+        checks: false,
+        job_logs: false,
+        job_log_limit: 4000,
+        follow_refs: None,
+        follow_refs_limit: 20,
+        attachments: false,
+        attachment_limit: 10 * 1024 * 1024,
+        attachments_total_limit: 50 * 1024 * 1024,
+        no_cache: false,
+        refresh: false,
+        cache_dir: None,
+        retries: 3,
+        max_retry_wait: 300,
+        pace: false,
+        jobs: 1,
+        record: None,
+        replay: None,
     }
 }
 
@@ -47,7 +77,7 @@ fn test_validate_bulk_args_rejects_clip_flag() {
 #[test]
 fn test_validate_bulk_args_rejects_per_page_zero() {
     let mut cli = make_cli();
-    cli.per_page = 0;
+    cli.per_page = Some(0);
     let err = validate_bulk_args(&cli).unwrap_err();
     assert!(err.to_string().contains("per-page"));
 }
@@ -55,7 +85,7 @@ fn test_validate_bulk_args_rejects_per_page_zero() {
 #[test]
 fn test_validate_bulk_args_rejects_per_page_overflow() {
     let mut cli = make_cli();
-    cli.per_page = 101;
+    cli.per_page = Some(101);
     let err = validate_bulk_args(&cli).unwrap_err();
     assert!(err.to_string().contains("per-page"));
 }
@@ -63,7 +93,7 @@ fn test_validate_bulk_args_rejects_per_page_overflow() {
 #[test]
 fn test_validate_bulk_args_rejects_zero_pages() {
     let mut cli = make_cli();
-    cli.pages = 0;
+    cli.pages = Some(0);
     let err = validate_bulk_args(&cli).unwrap_err();
     assert!(err.to_string().contains("pages"));
 }
@@ -98,6 +128,68 @@ fn test_resolve_bulk_out_dir_rejects_file() {
     let _ = fs::remove_file(&tmp_file);
 }
 
+#[test]
+fn test_validate_sync_args_requires_bulk() {
+    let mut cli = make_cli();
+    cli.sync = true;
+    assert!(validate_sync_args(&cli).is_ok());
+
+    cli.pages = Some(2);
+    let err = validate_sync_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("--per-page and --pages cannot be used with --sync"));
+
+    cli.pages = None;
+    cli.sync = false;
+    cli.sync_full = true;
+    let err = validate_sync_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("--sync-full requires --sync"));
+
+    cli.sync = true;
+    cli.sync_full = false;
+    cli.bulk = false;
+    let err = validate_sync_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("--sync requires --bulk"));
+}
+
+#[test]
+fn test_validate_resume_args() {
+    let mut cli = make_cli();
+    cli.resume = true;
+    assert!(validate_resume_args(&cli).is_ok());
+
+    cli.sync = true;
+    let err = validate_resume_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("--sync"));
+
+    cli.sync = false;
+    cli.bulk = false;
+    let err = validate_resume_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("requires --bulk or --from/--to"));
+
+    cli.from = Some(1);
+    cli.to = Some(3);
+    assert!(validate_resume_args(&cli).is_ok());
+}
+
+#[test]
+fn test_validate_jobs_args() {
+    let mut cli = make_cli();
+    cli.jobs = 8;
+    assert!(validate_jobs_args(&cli).is_ok());
+
+    cli.jobs = 0;
+    let err = validate_jobs_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("at least 1"));
+
+    cli.jobs = 8;
+    cli.bulk = false;
+    let err = validate_jobs_args(&cli).unwrap_err();
+    assert!(err.to_string().contains("requires --bulk or --from/--to"));
+
+    cli.jobs = 1;
+    assert!(validate_jobs_args(&cli).is_ok());
+}
+
 #[test]
 fn test_validate_pr_range_args_ok() {
     let mut cli = make_cli();
@@ -147,3 +239,49 @@ fn test_resolve_pr_range_out_dir_default() {
     assert!(dir.is_dir());
     let _ = fs::remove_dir_all(&dir);
 }
+
+#[test]
+fn test_fetch_options_checks_job_log_limit_only_with_job_logs() {
+    let mut cli = make_cli();
+    cli.job_log_limit = 100;
+    assert!(fetch_options(&cli).is_ok());
+
+    cli.checks = true;
+    cli.job_logs = true;
+    assert!(fetch_options(&cli).is_err());
+}
+
+#[test]
+fn test_invalid_args_exit_2_before_backend_setup() {
+    // The native backend needs a token, which must not be asked for first.
+    let invalid: Vec<fn(&mut Cli)> = vec![
+        |cli| {
+            cli.bulk = false;
+            cli.sync = true;
+        },
+        |cli| {
+            cli.bulk = false;
+            cli.from = Some(5);
+            cli.to = Some(1);
+        },
+        |cli| {
+            cli.no_cache = true;
+            cli.refresh = true;
+        },
+        |cli| cli.record = Some(PathBuf::from("cassette.json")),
+        |cli| cli.job_logs = true,
+        |cli| cli.per_page = Some(0),
+        |cli| cli.input = "owner".to_string(),
+    ];
+    for change in invalid {
+        let mut cli = make_cli();
+        cli.backend = BackendKind::Native;
+        change(&mut cli);
+        let err = run(cli).unwrap_err();
+        assert_eq!(ErrorKind::of(&err).exit_code(), 2, "{:#}", err);
+    }
+
+    let mut cli = make_cli();
+    cli.pace = true;
+    assert_eq!(ErrorKind::of(&run(cli).unwrap_err()), ErrorKind::InvalidArgs);
+}
@@ -0,0 +1,165 @@
+use super::fixtures::{target, temp_dir};
+use super::mock_server::{ok, MockServer, Route};
+use crate::backend::NativeBackend;
+use crate::cache::Cache;
+use crate::gh::{FetchOptions, TargetType};
+use std::fs;
+
+fn issue(title: &str, updated_at: &str) -> String {
+    serde_json::json!({
+        "number": 5,
+        "title": title,
+        "body": "It crashes.",
+        "html_url": "https://github.com/owner/repo/issues/5",
+        "user": {"login": "alice"},
+        "state": "open",
+        "updated_at": updated_at
+    })
+    .to_string()
+}
+
+fn with_etag(body: &str, etag: &str) -> Route {
+    Route {
+        etag: Some(etag.to_string()),
+        ..ok(body)
+    }
+}
+
+fn not_modified() -> Route {
+    Route {
+        status: 304,
+        ..ok("")
+    }
+}
+
+const ISSUE_PATH: &str = "/repos/owner/repo/issues/5";
+const COMMENTS_PATH: &str = "/repos/owner/repo/issues/5/comments?per_page=100";
+const COMMENTS: &str = r#"[{"user": {"login": "bob"}, "body": "Same here.", "created_at": "2024-01-02T00:00:00Z"}]"#;
+
+fn count(server: &MockServer, path: &str) -> usize {
+    server.paths().iter().filter(|p| *p == path).count()
+}
+
+#[test]
+fn test_cache_replays_unchanged_item() {
+    let v1 = issue("Crash on start", "2024-01-03T00:00:00Z");
+    let server = MockServer::start(vec![
+        // First run: view only; later runs: freshness check only, the
+        // second one with the ETag the first check returned
+        (ISSUE_PATH, ok(&v1)),
+        (ISSUE_PATH, with_etag(&v1, "\"v1\"")),
+        (ISSUE_PATH, not_modified()),
+        (COMMENTS_PATH, ok(COMMENTS)),
+        ("/repos/owner/repo/issues/5/timeline?per_page=100", ok("[]")),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let dir = temp_dir("cache-replay");
+    let cache = Cache::new(dir.to_path_buf(), false);
+
+    let first = cache.fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default()).unwrap();
+    assert_eq!(count(&server, ISSUE_PATH), 1);
+    cache.fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default()).unwrap();
+    let third = cache.fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default()).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&first).unwrap(),
+        serde_json::to_value(&third).unwrap()
+    );
+    assert_eq!(third.comments[0].body, "Same here.");
+    assert_eq!(server.paths().len(), 5);
+    assert_eq!(count(&server, ISSUE_PATH), 3);
+    let last = server.requests.lock().unwrap().pop().unwrap();
+    assert_eq!(last.path, ISSUE_PATH);
+    assert_eq!(last.if_none_match.as_deref(), Some("\"v1\""));
+    assert!(dir.join("github.com/owner/repo/issue-5.json").is_file());
+}
+
+#[test]
+fn test_cache_refetches_updated_item() {
+    let v1 = issue("Crash on start", "2024-01-03T00:00:00Z");
+    let v2 = issue("Crash on start (Windows only)", "2024-01-04T00:00:00Z");
+    let server = MockServer::start(vec![
+        (ISSUE_PATH, ok(&v1)),
+        (ISSUE_PATH, with_etag(&v2, "\"v2\"")),
+        (ISSUE_PATH, ok(&v2)),
+        (COMMENTS_PATH, ok(COMMENTS)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let dir = temp_dir("cache-updated");
+    let cache = Cache::new(dir.to_path_buf(), false);
+
+    cache.fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default()).unwrap();
+    let second = cache.fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default()).unwrap();
+
+    assert_eq!(second.title, "Crash on start (Windows only)");
+    assert_eq!(count(&server, COMMENTS_PATH), 2);
+}
+
+#[test]
+fn test_cache_refresh_ignores_stored_responses() {
+    let v1 = issue("Crash on start", "2024-01-03T00:00:00Z");
+    let server = MockServer::start(vec![
+        (ISSUE_PATH, with_etag(&v1, "\"v1\"")),
+        (COMMENTS_PATH, ok(COMMENTS)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let dir = temp_dir("cache-refresh");
+
+    Cache::new(dir.to_path_buf(), false)
+        .fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default())
+        .unwrap();
+    Cache::new(dir.to_path_buf(), true)
+        .fetch_context(&backend, &target(TargetType::Issue, 5), &FetchOptions::default())
+        .unwrap();
+
+    assert_eq!(count(&server, COMMENTS_PATH), 2);
+    assert!(server
+        .requests
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|r| r.if_none_match.is_none()));
+}
+
+#[test]
+fn test_cache_checks_referenced_items_separately() {
+    let parent = serde_json::json!({
+        "number": 5, "title": "Crash on start", "body": "Same as #6.",
+        "html_url": "https://github.com/owner/repo/issues/5", "user": {"login": "alice"},
+        "state": "open", "updated_at": "2024-01-03T00:00:00Z"
+    })
+    .to_string();
+    let v1 = serde_json::json!({
+        "number": 6, "title": "Crash", "body": "", "html_url": "https://github.com/owner/repo/issues/6",
+        "user": {"login": "bob"}, "state": "open", "updated_at": "2024-01-01T00:00:00Z"
+    })
+    .to_string();
+    let v2 = v1.replace("\"Crash\"", "\"Crash on Windows\"").replace("2024-01-01", "2024-01-05");
+    let server = MockServer::start(vec![
+        (ISSUE_PATH, ok(&parent)),
+        (ISSUE_PATH, with_etag(&parent, "\"p1\"")),
+        // First run: kind lookup and view; second run: kind lookup, check and view
+        ("/repos/owner/repo/issues/6", ok(&v1)),
+        ("/repos/owner/repo/issues/6", ok(&v1)),
+        ("/repos/owner/repo/issues/6", ok(&v2)),
+        ("/repos/owner/repo/issues/6", ok(&v2)),
+        ("/repos/owner/repo/issues/6", ok(&v2)),
+        ("/repos/owner/repo/issues/6/comments?per_page=100", ok("[]")),
+        (COMMENTS_PATH, ok(COMMENTS)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let dir = temp_dir("cache-refs");
+    let cache = Cache::new(dir.to_path_buf(), false);
+    let options = FetchOptions::builder().follow_refs(1, 10).build().unwrap();
+
+    cache.fetch_context(&backend, &target(TargetType::Issue, 5), &options).unwrap();
+    let second = cache.fetch_context(&backend, &target(TargetType::Issue, 5), &options).unwrap();
+
+    assert_eq!(second.references[0].context.title, "Crash on Windows");
+    // The unchanged parent is replayed; the changed reference is not.
+    assert_eq!(count(&server, COMMENTS_PATH), 1);
+    assert_eq!(count(&server, "/repos/owner/repo/issues/6/comments?per_page=100"), 2);
+    assert!(dir.join("github.com/owner/repo/issue-6.json").is_file());
+    let parent_entry = fs::read_to_string(dir.join("github.com/owner/repo/issue-5.json")).unwrap();
+    assert!(!parent_entry.contains("issues/6"));
+}
@@ -0,0 +1,40 @@
+use super::fixtures::temp_dir;
+use crate::checkpoint::{Checkpoint, MANIFEST_FILE};
+use std::fs;
+
+#[test]
+fn test_checkpoint_resume_skips_done_items() {
+    let dir = temp_dir("checkpoint-resume");
+    let run = "range owner/repo 1..=4";
+
+    let mut checkpoint = Checkpoint::start(&dir, run, &[1, 2, 3, 4]).unwrap();
+    fs::write(dir.join("repo-pr-1.md"), "").unwrap();
+    checkpoint.done(1, "repo-pr-1.md").unwrap();
+    checkpoint.failed(2, "HTTP 502").unwrap();
+    // 3 was written but its file has since been deleted
+    checkpoint.done(3, "repo-pr-3.md").unwrap();
+    drop(checkpoint);
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join(MANIFEST_FILE)).unwrap()).unwrap();
+    assert_eq!(manifest["items"]["1"]["status"], "done");
+    assert_eq!(manifest["items"]["2"]["status"], "failed");
+    assert_eq!(manifest["items"]["2"]["error"], "HTTP 502");
+    assert_eq!(manifest["items"]["4"]["status"], "pending");
+
+    let resumed = Checkpoint::resume(&dir, run).unwrap();
+    assert_eq!(resumed.total(), 4);
+    assert_eq!(resumed.remaining(), vec![2, 3, 4]);
+}
+
+#[test]
+fn test_checkpoint_resume_rejects_other_run() {
+    let dir = temp_dir("checkpoint-other");
+
+    let err = Checkpoint::resume(&dir, "range owner/repo 1..=4").unwrap_err();
+    assert!(err.to_string().contains("Nothing to resume"));
+
+    Checkpoint::start(&dir, "range owner/repo 1..=4", &[1, 2, 3, 4]).unwrap();
+    let err = Checkpoint::resume(&dir, "range owner/repo 1..=9").unwrap_err();
+    assert!(err.to_string().contains("different run (range owner/repo 1..=4)"));
+}
@@ -0,0 +1,67 @@
+use crate::diff::{split_patch, DiffFilter};
+
+const PATCH: &str = "diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1 +1 @@
+-fn main() {}
++fn main() { run(); }
+diff --git a/Cargo.lock b/Cargo.lock
+index 3333333..4444444 100644
+--- a/Cargo.lock
++++ b/Cargo.lock
+@@ -1 +1 @@
+-version = 3
++version = 4
+";
+
+#[test]
+fn test_split_patch_per_file() {
+    let files = split_patch(PATCH);
+    assert_eq!(files.len(), 2);
+    assert_eq!(files[0].path, "src/main.rs");
+    assert!(files[0].patch.starts_with("diff --git a/src/main.rs"));
+    assert!(files[0].patch.contains("+fn main() { run(); }"));
+    assert!(!files[0].patch.contains("Cargo.lock"));
+    assert_eq!(files[1].path, "Cargo.lock");
+}
+
+#[test]
+fn test_split_patch_uses_new_path_for_renames() {
+    let patch = "diff --git a/old/name.rs b/new/name.rs\nsimilarity index 100%\n";
+    let files = split_patch(patch);
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "new/name.rs");
+}
+
+#[test]
+fn test_split_patch_empty() {
+    assert!(split_patch("").is_empty());
+}
+
+#[test]
+fn test_diff_filter_exclude() {
+    let filter = DiffFilter::new(&[], &["*.lock".to_string()]).unwrap();
+    assert!(filter.matches("src/main.rs"));
+    assert!(!filter.matches("Cargo.lock"));
+    assert!(!filter.matches("nested/yarn.lock"));
+}
+
+#[test]
+fn test_diff_filter_include_and_exclude() {
+    let filter = DiffFilter::new(
+        &["src/**".to_string()],
+        &["src/generated/**".to_string()],
+    )
+    .unwrap();
+    assert!(filter.matches("src/main.rs"));
+    assert!(!filter.matches("src/generated/schema.rs"));
+    assert!(!filter.matches("README.md"));
+}
+
+#[test]
+fn test_diff_filter_rejects_invalid_glob() {
+    let err = DiffFilter::new(&["[".to_string()], &[]).unwrap_err();
+    assert!(err.to_string().contains("Invalid diff glob"));
+}
@@ -0,0 +1,104 @@
+use super::mock_server::{MockServer, Route};
+use crate::backend::{gh_failure, Backend, NativeBackend};
+use crate::error::{ErrorKind, GhError};
+use crate::gh::parse_target;
+use anyhow::{anyhow, Context};
+
+fn kind_of_gh(stderr: &str) -> Option<ErrorKind> {
+    gh_failure(stderr, stderr.to_string()).map(|err| err.kind())
+}
+
+#[test]
+fn test_error_kind_found_through_context() {
+    let err = anyhow::Error::new(GhError::rate_limited("API rate limit exceeded".to_string()))
+        .context("Failed to fetch issue #3");
+    assert_eq!(ErrorKind::of(&err), ErrorKind::RateLimited);
+    assert_eq!(ErrorKind::of(&err).exit_code(), 7);
+
+    let err = serde_json::from_str::<u64>("\"x\"")
+        .context("Failed to parse JSON output from 'gh'")
+        .unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::Parse);
+
+    assert_eq!(ErrorKind::of(&anyhow!("something else")), ErrorKind::Other);
+    assert_eq!(ErrorKind::Other.exit_code(), 1);
+
+    let err = parse_target("owner/repo#12", false, false).unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::InvalidArgs);
+    assert_eq!(ErrorKind::of(&err).exit_code(), 2);
+}
+
+#[test]
+fn test_error_from_status() {
+    let kind = |status, message: &str, exhausted| {
+        GhError::from_status(status, message.to_string(), exhausted, None).map(|e| e.kind())
+    };
+    assert_eq!(kind(404, "Not Found", false), Some(ErrorKind::NotFound));
+    assert_eq!(kind(401, "Bad credentials", false), Some(ErrorKind::AuthRequired));
+    assert_eq!(kind(403, "Resource not accessible by integration", false), Some(ErrorKind::Forbidden));
+    assert_eq!(kind(403, "API rate limit exceeded for user", false), Some(ErrorKind::RateLimited));
+    assert_eq!(kind(403, "Forbidden", true), Some(ErrorKind::RateLimited));
+    assert_eq!(kind(429, "Too Many Requests", false), Some(ErrorKind::RateLimited));
+    assert_eq!(kind(500, "Server Error", false), None);
+    assert_eq!(kind(502, "Bad Gateway", false), Some(ErrorKind::Network));
+}
+
+#[test]
+fn test_gh_cli_failures() {
+    assert_eq!(kind_of_gh("gh: Not Found (HTTP 404)"), Some(ErrorKind::NotFound));
+    assert_eq!(
+        kind_of_gh("GraphQL: Could not resolve to an Issue with the number of 9. (repository.issue)"),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(kind_of_gh("HTTP 401: Bad credentials (https://api.github.com/graphql)"), Some(ErrorKind::AuthRequired));
+    assert_eq!(
+        kind_of_gh("To get started with GitHub CLI, please run:  gh auth login"),
+        Some(ErrorKind::AuthRequired)
+    );
+    assert_eq!(kind_of_gh("gh: API rate limit exceeded for user ID 1. (HTTP 403)"), Some(ErrorKind::RateLimited));
+    assert_eq!(kind_of_gh("HTTP 403: Must have admin rights to Repository."), Some(ErrorKind::Forbidden));
+    assert_eq!(
+        kind_of_gh("error connecting to api.github.com\ncheck your internet connection"),
+        Some(ErrorKind::Network)
+    );
+    assert_eq!(kind_of_gh("unknown flag: --bogus"), None);
+}
+
+#[test]
+fn test_native_backend_raises_typed_errors() {
+    let server = MockServer::start(vec![
+        (
+            "/repos/owner/private",
+            Route {
+                status: 403,
+                body: r#"{"message":"Resource not accessible by integration"}"#.to_string(),
+                link: None,
+                etag: None,
+                retry_after: None,
+            },
+        ),
+        (
+            "/graphql",
+            Route {
+                status: 200,
+                body: r#"{"data":null,"errors":[{"type":"NOT_FOUND","message":"Could not resolve to a Repository"}]}"#
+                    .to_string(),
+                link: None,
+                etag: None,
+                retry_after: None,
+            },
+        ),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let err = backend.rest("repos/owner/missing").unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::NotFound);
+    let err = backend.rest("repos/owner/private").unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::Forbidden);
+    let err = backend.graphql("query { viewer { login } }", &[]).unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::NotFound);
+
+    let unreachable = NativeBackend::new("http://127.0.0.1:1", "secret");
+    let err = unreachable.rest("repos/owner/repo").unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::Network);
+}
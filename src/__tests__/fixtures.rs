@@ -0,0 +1,49 @@
+use crate::gh::{Target, TargetType};
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// `owner/repo` item `number` on github.com.
+pub fn target(kind: TargetType, number: u64) -> Target {
+    Target {
+        host: "github.com".to_string(),
+        owner: "owner".to_string(),
+        repo: "repo".to_string(),
+        number: Some(number),
+        kind,
+    }
+}
+
+/// `owner/repo@sha` on github.com.
+pub fn commit_target(sha: &str) -> Target {
+    Target {
+        number: None,
+        ..target(TargetType::Commit { sha: sha.to_string() }, 0)
+    }
+}
+
+/// A fresh, empty directory under the system temp dir, removed on drop so it
+/// is cleaned up even when an assertion fails.
+pub struct TempDir(PathBuf);
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Creates `gh-context-<name>-<pid>` in the system temp dir, emptying it first.
+pub fn temp_dir(name: &str) -> TempDir {
+    let dir = std::env::temp_dir().join(format!("gh-context-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TempDir(dir)
+}
@@ -0,0 +1,467 @@
+use crate::format::{render, to_json, to_markdown, to_markdown_chronological, Format};
+use crate::gh::to_timeline_event;
+use crate::types::{
+    CheckAnnotation, CheckRun, Checks, Commit, CommitStatus, Context, EventKind, FileDiff,
+    IssueReference, Metadata, Review, ReviewThread, TimelineEvent, UnifiedComment,
+};
+use crate::wire::GhTimelineEvent;
+
+fn make_context() -> Context {
+    Context {
+        metadata: Metadata {
+            host: "github.com".to_string(),
+            repo: "owner/repo".to_string(),
+            number: Some(7),
+            sha: None,
+            r#type: "pr".to_string(),
+            url: "https://github.com/owner/repo/pull/7".to_string(),
+            author: "octocat".to_string(),
+            state: None,
+            state_reason: None,
+            draft: None,
+            labels: Vec::new(),
+            assignees: Vec::new(),
+            milestone: None,
+            created_at: None,
+            updated_at: None,
+            closed_at: None,
+            merged_at: None,
+            base_branch: None,
+            head_branch: None,
+            category: None,
+            locked: false,
+        },
+        title: "Add feature".to_string(),
+        body: "Body text".to_string(),
+        comments: Vec::new(),
+        events: Vec::new(),
+        commits: None,
+        reviews: None,
+        review_threads: None,
+        checks: None,
+        diff: None,
+        pull_requests: None,
+        references: Vec::new(),
+    }
+}
+
+fn make_comment(author: &str, body: &str) -> UnifiedComment {
+    UnifiedComment {
+        author: author.to_string(),
+        body: body.to_string(),
+        created_at: None,
+        is_answer: false,
+        replies: Vec::new(),
+    }
+}
+
+fn make_event(actor: &str, kind: EventKind) -> TimelineEvent {
+    TimelineEvent {
+        actor: actor.to_string(),
+        created_at: Some("2024-01-01T00:00:00Z".to_string()),
+        kind,
+    }
+}
+
+fn make_thread(path: &str, line: Option<u64>) -> ReviewThread {
+    ReviewThread {
+        path: path.to_string(),
+        line,
+        start_line: None,
+        original_line: Some(3),
+        original_start_line: None,
+        diff_hunk: "@@ -1,3 +1,3 @@\n-old\n+new".to_string(),
+        is_resolved: false,
+        is_outdated: line.is_none(),
+        comments: vec![make_comment("alice", "Why?"), make_comment("bob", "Because.")],
+    }
+}
+
+#[test]
+fn test_markdown_groups_review_threads_by_file() {
+    let mut context = make_context();
+    context.review_threads = Some(vec![
+        make_thread("src/b.rs", Some(10)),
+        make_thread("src/a.rs", None),
+        make_thread("src/b.rs", Some(20)),
+    ]);
+
+    let md = to_markdown(&context);
+    let a = md.find("### src/a.rs").unwrap();
+    let b = md.find("### src/b.rs").unwrap();
+    assert!(a < b);
+    assert_eq!(md.matches("### src/b.rs").count(), 1);
+    assert!(md.contains("#### Line 10\n"));
+    assert!(md.contains("#### Originally line 3 (outdated)"));
+    assert!(md.contains("**Reply by bob**"));
+}
+
+#[test]
+fn test_markdown_metadata_table() {
+    let mut context = make_context();
+    context.metadata.state = Some("CLOSED".to_string());
+    context.metadata.state_reason = Some("NOT_PLANNED".to_string());
+    context.metadata.labels = vec!["bug".to_string(), "a|b".to_string()];
+    context.metadata.base_branch = Some("main".to_string());
+    context.metadata.locked = true;
+
+    let md = to_markdown(&context);
+    assert!(md.contains("| Author | octocat |\n"));
+    assert!(md.contains("| State | CLOSED (NOT_PLANNED) |\n"));
+    assert!(md.contains("| Labels | bug, a\\|b |\n"));
+    assert!(md.contains("| Base branch | main |\n"));
+    assert!(md.contains("| Locked | yes |\n"));
+    assert!(!md.contains("| Milestone |"));
+}
+
+#[test]
+fn test_markdown_commits() {
+    let mut context = make_context();
+    context.commits = Some(vec![
+        Commit {
+            sha: "0123456789abcdef".to_string(),
+            authors: vec!["alice".to_string(), "Bob Smith".to_string()],
+            authored_at: Some("2024-01-02T03:04:05Z".to_string()),
+            headline: "Fix parser".to_string(),
+            message: "Fix parser\n\nThe old one choked on tabs.".to_string(),
+        },
+        Commit {
+            sha: "fedcba9876543210".to_string(),
+            authors: vec!["alice".to_string()],
+            authored_at: None,
+            headline: "Bump version".to_string(),
+            message: "Bump version".to_string(),
+        },
+    ]);
+
+    let md = to_markdown(&context);
+    assert!(md.contains("### `0123456` Fix parser\n_alice, Bob Smith at 2024-01-02T03:04:05Z_\n\nThe old one choked on tabs.\n"));
+    assert!(md.contains("### `fedcba9` Bump version\n_alice_\n\n## "));
+}
+
+#[test]
+fn test_markdown_commit_headline_not_prefix_of_message() {
+    let mut context = make_context();
+    context.commits = Some(vec![Commit {
+        sha: "0123456789abcdef".to_string(),
+        authors: vec!["alice".to_string()],
+        authored_at: None,
+        headline: "Réparer l'analyseur…".to_string(),
+        message: "Fix".to_string(),
+    }]);
+
+    let md = to_markdown(&context);
+    assert!(md.contains("### `0123456` Réparer l'analyseur…\n_alice_\n\nFix\n"));
+}
+
+fn make_review(author: &str, state: &str, body: &str) -> Review {
+    Review {
+        author: author.to_string(),
+        state: state.to_string(),
+        submitted_at: None,
+        body: body.to_string(),
+    }
+}
+
+#[test]
+fn test_markdown_reviews_show_latest_verdict_per_reviewer() {
+    let mut context = make_context();
+    context.reviews = Some(vec![
+        make_review("alice", "CHANGES_REQUESTED", "Please fix the tests."),
+        make_review("bob", "COMMENTED", ""),
+        make_review("alice", "APPROVED", ""),
+        make_review("alice", "COMMENTED", "One more nit."),
+    ]);
+
+    let md = to_markdown(&context);
+    assert!(md.contains("- **alice**: APPROVED\n- **bob**: COMMENTED\n"));
+    assert!(md.contains("### CHANGES_REQUESTED by alice"));
+    assert!(md.contains("### COMMENTED by alice"));
+    assert!(!md.contains("### COMMENTED by bob"));
+}
+
+#[test]
+fn test_markdown_omits_pr_sections_for_issues() {
+    let md = to_markdown(&make_context());
+    assert!(!md.contains("## Reviews"));
+    assert!(!md.contains("## Review Threads"));
+    assert!(!md.contains("## Diff"));
+}
+
+fn make_check_run(name: &str, conclusion: Option<&str>) -> CheckRun {
+    CheckRun {
+        id: 1,
+        name: name.to_string(),
+        status: if conclusion.is_some() { "completed" } else { "in_progress" }.to_string(),
+        conclusion: conclusion.map(str::to_string),
+        url: None,
+        app: Some("github-actions".to_string()),
+        annotations: Vec::new(),
+        log_excerpt: None,
+    }
+}
+
+#[test]
+fn test_markdown_checks_lists_failures_first() {
+    let mut failing = make_check_run("test", Some("failure"));
+    failing.annotations.push(CheckAnnotation {
+        path: "src/lib.rs".to_string(),
+        start_line: Some(4),
+        end_line: Some(6),
+        level: "failure".to_string(),
+        title: None,
+        message: "assertion failed".to_string(),
+    });
+
+    let mut context = make_context();
+    context.checks = Some(Checks {
+        head_sha: "abc123".to_string(),
+        check_runs: vec![
+            make_check_run("lint", Some("success")),
+            make_check_run("build", None),
+            failing,
+        ],
+        statuses: vec![CommitStatus {
+            context: "ci/legacy".to_string(),
+            state: "error".to_string(),
+            description: Some("Timed out".to_string()),
+            url: None,
+        }],
+    });
+
+    let md = to_markdown(&context);
+    assert!(md.contains("2 failing, 1 pending, 4 total"));
+    let test = md.find("- **test**: failure").unwrap();
+    let lint = md.find("- **lint**: success").unwrap();
+    assert!(test < lint);
+    assert!(md.contains("  - `src/lib.rs:4-6` [failure] assertion failed\n"));
+    assert!(md.contains("- **build**: in_progress"));
+    assert!(md.contains("- **ci/legacy**: error - Timed out"));
+}
+
+#[test]
+fn test_markdown_diff_fence_outlasts_patch_backticks() {
+    let mut context = make_context();
+    context.diff = Some(vec![FileDiff {
+        path: "README.md".to_string(),
+        patch: "+```bash\n+cargo run\n+```\n".to_string(),
+    }]);
+
+    let md = to_markdown(&context);
+    assert!(md.contains("### README.md\n\n````diff\n"));
+}
+
+#[test]
+fn test_json_skips_absent_sections() {
+    let json = to_json(&make_context()).unwrap();
+    assert!(!json.contains("review_threads"));
+    assert!(!json.contains("\"diff\""));
+}
+
+#[test]
+fn test_markdown_describes_timeline_events() {
+    let mut context = make_context();
+    context.events = vec![
+        make_event("bob", EventKind::Labeled { label: "bug".to_string() }),
+        make_event(
+            "bob",
+            EventKind::Renamed {
+                from: "Crash".to_string(),
+                to: "Crash on start".to_string(),
+            },
+        ),
+        make_event(
+            "carol",
+            EventKind::CrossReferenced {
+                source: IssueReference {
+                    repo: "owner/other".to_string(),
+                    number: 12,
+                    title: "Follow-up".to_string(),
+                    url: "https://github.com/owner/other/issues/12".to_string(),
+                },
+            },
+        ),
+        make_event(
+            "alice",
+            EventKind::Closed {
+                commit: Some("0123456789abcdef".to_string()),
+                state_reason: Some("not_planned".to_string()),
+            },
+        ),
+        make_event("alice", EventKind::Other { event: "pinned".to_string() }),
+        make_event(
+            "Alice A",
+            EventKind::Committed {
+                commit: Some("fedcba9876543210".to_string()),
+            },
+        ),
+        make_event(
+            "dave",
+            EventKind::Reviewed {
+                state: Some("changes_requested".to_string()),
+            },
+        ),
+    ];
+
+    let md = to_markdown(&context);
+    assert!(md.contains("- **bob** added label `bug` at 2024-01-01T00:00:00Z\n"));
+    assert!(md.contains("- **bob** changed the title from \"Crash\" to \"Crash on start\""));
+    assert!(md.contains(
+        "- **carol** mentioned this in [owner/other#12](https://github.com/owner/other/issues/12): Follow-up"
+    ));
+    assert!(md.contains("- **alice** closed this as not planned in `0123456`"));
+    assert!(md.contains("- **alice** `pinned` at"));
+    assert!(md.contains("- **Alice A** added commit `fedcba9` at"));
+    assert!(md.contains("- **dave** requested changes at"));
+}
+
+#[test]
+fn test_json_timeline_events_keep_rest_names() {
+    let mut context = make_context();
+    context.events = vec![
+        make_event("bob", EventKind::ReviewRequested { reviewer: "dave".to_string() }),
+        make_event("bob", EventKind::Reopened),
+        make_event("bob", EventKind::Other { event: "pinned".to_string() }),
+    ];
+
+    let json: serde_json::Value = serde_json::from_str(&to_json(&context).unwrap()).unwrap();
+    let events = json["events"].as_array().unwrap();
+    assert_eq!(events[0]["event"], "review_requested");
+    assert_eq!(events[0]["reviewer"], "dave");
+    assert_eq!(events[0]["actor"], "bob");
+    assert_eq!(events[1]["event"], "reopened");
+    assert_eq!(events[2]["event"], "pinned");
+}
+
+// Comments and raw REST timeline events of a saved sample, the latter with
+// a `commented` event for every comment.
+fn sample_context(json: &str) -> Context {
+    let sample: serde_json::Value = serde_json::from_str(json).unwrap();
+    let mut context = make_context();
+    for comment in sample["comments"].as_array().unwrap() {
+        let mut unified = make_comment(
+            comment["author"].as_str().unwrap(),
+            comment["body"].as_str().unwrap(),
+        );
+        unified.created_at = comment["created_at"].as_str().map(str::to_string);
+        context.comments.push(unified);
+    }
+    context.events = sample["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| to_timeline_event(serde_json::from_value::<GhTimelineEvent>(e.clone()).unwrap()))
+        .collect();
+    context
+}
+
+#[test]
+fn test_markdown_chronological_samples_list_comments_once_in_order() {
+    let samples = [
+        include_str!("../../samples/brave-browser-issue-5717/brave-browser-issue-5717.json"),
+        include_str!("../../samples/android-pr-6237/android-pr-6237.json"),
+    ];
+    for json in samples {
+        let context = sample_context(json);
+        assert!(context.events.iter().any(|e| matches!(e.kind, EventKind::Commented)));
+        let md = to_markdown_chronological(&context);
+        let timeline = &md[md.find("## Timeline").unwrap()..];
+
+        assert_eq!(timeline.matches("### Comment ").count(), context.comments.len());
+        assert!(!timeline.contains(" commented at "));
+
+        // Comment dates sit under their heading; event dates end the line.
+        let lines: Vec<&str> = timeline.lines().collect();
+        let mut dates = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_with("### Comment ") {
+                dates.push(lines[i + 1].trim_matches('_'));
+            } else if line.starts_with("- **") {
+                dates.push(line.rsplit(" at ").next().unwrap());
+            }
+        }
+        assert!(dates.len() > context.comments.len());
+        assert!(!dates.contains(&"-"), "undated entries trail the timeline");
+        assert!(dates.is_sorted(), "timeline out of order");
+    }
+}
+
+#[test]
+fn test_markdown_chronological_interleaves_comments_and_events() {
+    let mut context = make_context();
+    let mut first = make_comment("alice", "First!");
+    first.created_at = Some("2024-01-01T00:00:00Z".to_string());
+    let mut second = make_comment("bob", "Still broken.");
+    second.created_at = Some("2024-01-03T00:00:00Z".to_string());
+    context.comments = vec![first, second];
+    let mut closed = make_event(
+        "carol",
+        EventKind::Closed {
+            commit: None,
+            state_reason: None,
+        },
+    );
+    closed.created_at = Some("2024-01-02T00:00:00Z".to_string());
+    context.events = vec![closed];
+
+    let md = to_markdown_chronological(&context);
+    let first = md.find("### Comment 1 by alice").unwrap();
+    let closed = md.find("- **carol** closed this").unwrap();
+    let second = md.find("### Comment 2 by bob").unwrap();
+    assert!(first < closed && closed < second);
+    assert!(md.contains("at 2024-01-02T00:00:00Z\n\n### Comment 2"));
+    assert!(md.contains("## Timeline\n"));
+    assert!(!md.contains("## Comments"));
+    assert!(!md.contains("## Timeline Events"));
+}
+
+#[test]
+fn test_markdown_sections_layout_is_default() {
+    let mut context = make_context();
+    context.comments = vec![make_comment("alice", "First!")];
+    context.events = vec![make_event("bob", EventKind::Reopened)];
+
+    let md = to_markdown(&context);
+    let comments = md.find("## Comments").unwrap();
+    let events = md.find("## Timeline Events").unwrap();
+    assert!(comments < events);
+}
+
+#[test]
+fn test_markdown_discussion_replies_and_answer() {
+    let mut context = make_context();
+    context.metadata.r#type = "discussion".to_string();
+    context.metadata.category = Some("Ideas".to_string());
+    let mut question = make_comment("alice", "Should we split the crate?");
+    question.replies = vec![make_comment("bob", "Yes."), make_comment("carol", "No.")];
+    let mut answer = make_comment("dave", "Split after 1.0.");
+    answer.is_answer = true;
+    context.comments = vec![question, answer];
+
+    let md = to_markdown(&context);
+    assert!(md.contains("| Category | Ideas |"));
+    let comment = md.find("### Comment 1 by alice\n").unwrap();
+    let first = md.find("#### Reply 1.1 by bob\n").unwrap();
+    let second = md.find("#### Reply 1.2 by carol\n").unwrap();
+    let next = md.find("### Comment 2 by dave (accepted answer)\n").unwrap();
+    assert!(comment < first && first < second && second < next);
+
+    let json: serde_json::Value = serde_json::from_str(&to_json(&context).unwrap()).unwrap();
+    assert_eq!(json["comments"][0]["replies"][1]["author"], "carol");
+    assert!(json["comments"][0].get("is_answer").is_none());
+    assert_eq!(json["comments"][1]["is_answer"], true);
+    assert!(json["comments"][1].get("replies").is_none());
+}
+
+#[test]
+fn test_render_formats() {
+    let context = make_context();
+    assert_eq!(render(&context, Format::Json).unwrap(), to_json(&context).unwrap());
+    assert_eq!(render(&context, Format::Markdown).unwrap(), to_markdown(&context));
+    assert_eq!(
+        render(&context, Format::MarkdownChronological).unwrap(),
+        to_markdown_chronological(&context)
+    );
+    assert_eq!(Format::Json.extension(), "json");
+    assert_eq!(Format::MarkdownChronological.extension(), "md");
+}
@@ -1,4 +1,4 @@
-use crate::gh::{parse_repo, parse_target, TargetType};
+use crate::gh::{parse_host, parse_repo, parse_target, FetchOptions, TargetType};
 
 #[test]
 fn test_parse_full_url_issue() {
@@ -6,8 +6,9 @@ fn test_parse_full_url_issue() {
     let target = parse_target(input, false, false).unwrap();
     assert_eq!(target.owner, "rust-lang");
     assert_eq!(target.repo, "rust");
-    assert_eq!(target.number, 123);
+    assert_eq!(target.number, Some(123));
     assert!(matches!(target.kind, TargetType::Issue));
+    assert_eq!(target.host, "github.com");
 }
 
 #[test]
@@ -24,7 +25,7 @@ fn test_parse_full_url_pr() {
     let target = parse_target(input, false, false).unwrap();
     assert_eq!(target.owner, "rust-lang");
     assert_eq!(target.repo, "rust");
-    assert_eq!(target.number, 456);
+    assert_eq!(target.number, Some(456));
     assert!(matches!(target.kind, TargetType::Pr));
 }
 
@@ -41,7 +42,7 @@ fn test_parse_shorthand_forced_issue() {
     let target = parse_target(input, true, false).unwrap();
     assert_eq!(target.owner, "rust-lang");
     assert_eq!(target.repo, "rust");
-    assert_eq!(target.number, 789);
+    assert_eq!(target.number, Some(789));
     assert!(matches!(target.kind, TargetType::Issue));
 }
 
@@ -51,7 +52,7 @@ fn test_parse_shorthand_forced_pr() {
     let target = parse_target(input, false, true).unwrap();
     assert_eq!(target.owner, "rust-lang");
     assert_eq!(target.repo, "rust");
-    assert_eq!(target.number, 789);
+    assert_eq!(target.number, Some(789));
     assert!(matches!(target.kind, TargetType::Pr));
 }
 
@@ -59,21 +60,21 @@ fn test_parse_shorthand_forced_pr() {
 fn test_invalid_url() {
     let input = "https://github.com/rust-lang/rust/blob/main/README.md";
     let err = parse_target(input, false, false).unwrap_err();
-    assert!(err.to_string().contains("URL must contain 'issues' or 'pull'"));
+    assert!(err.to_string().contains("URL must contain 'issues', 'pull' or 'discussions'"));
 }
 
 #[test]
 fn test_parse_full_url_with_fragment() {
     let input = "https://github.com/rust-lang/rust/issues/123#issuecomment-456";
     let target = parse_target(input, false, false).unwrap();
-    assert_eq!(target.number, 123);
+    assert_eq!(target.number, Some(123));
 }
 
 #[test]
 fn test_parse_full_url_with_query() {
     let input = "https://github.com/rust-lang/rust/pull/789?w=1";
     let target = parse_target(input, false, false).unwrap();
-    assert_eq!(target.number, 789);
+    assert_eq!(target.number, Some(789));
 }
 
 #[test]
@@ -113,3 +114,109 @@ fn test_parse_repo_rejects_issue_number() {
     let err = parse_repo(input).unwrap_err();
     assert!(err.to_string().contains("issue number"));
 }
+
+#[test]
+fn test_parse_enterprise_url_pr() {
+    let input = "https://GHE.Example.com/platform/api/pull/42";
+    let target = parse_target(input, false, false).unwrap();
+    assert_eq!(target.host, "ghe.example.com");
+    assert_eq!(target.owner, "platform");
+    assert_eq!(target.repo, "api");
+    assert_eq!(target.number, Some(42));
+    assert!(matches!(target.kind, TargetType::Pr));
+}
+
+#[test]
+fn test_parse_host() {
+    assert_eq!(
+        parse_host("https://www.github.com/rust-lang/rust/issues/1").as_deref(),
+        Some("github.com")
+    );
+    assert_eq!(
+        parse_host("https://ghe.example.com/platform/api/issues").as_deref(),
+        Some("ghe.example.com")
+    );
+    assert_eq!(parse_host("rust-lang/rust#1"), None);
+}
+
+#[test]
+fn test_parse_repo_enterprise_url() {
+    let input = "https://ghe.example.com/platform/api/issues";
+    let (owner, repo) = parse_repo(input).unwrap();
+    assert_eq!(owner, "platform");
+    assert_eq!(repo, "api");
+}
+
+#[test]
+fn test_parse_full_url_discussion() {
+    let input = "https://github.com/rust-lang/rust/discussions/789";
+    let target = parse_target(input, false, false).unwrap();
+    assert_eq!(target.number, Some(789));
+    assert!(matches!(target.kind, TargetType::Discussion));
+}
+
+#[test]
+fn test_parse_commit_url() {
+    let input = "https://github.com/rust-lang/rust/commit/0123456789ABCDEF0123456789abcdef01234567";
+    let target = parse_target(input, false, false).unwrap();
+    assert_eq!(target.owner, "rust-lang");
+    assert_eq!(target.repo, "rust");
+    assert_eq!(
+        target.kind,
+        TargetType::Commit {
+            sha: "0123456789abcdef0123456789abcdef01234567".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_parse_pr_commit_url() {
+    let input = "https://github.com/rust-lang/rust/pull/456/commits/abc1234#diff-1";
+    let target = parse_target(input, false, false).unwrap();
+    assert_eq!(
+        target.kind,
+        TargetType::Commit {
+            sha: "abc1234".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_parse_commit_shorthand() {
+    let target = parse_target("rust-lang/rust@abc1234", false, false).unwrap();
+    assert_eq!(target.owner, "rust-lang");
+    assert_eq!(target.repo, "rust");
+    assert_eq!(target.number, None);
+    assert!(target.item_number().is_err());
+    assert_eq!(
+        target.kind,
+        TargetType::Commit {
+            sha: "abc1234".to_string()
+        }
+    );
+
+    let err = parse_target("rust-lang/rust@main", false, false).unwrap_err();
+    assert!(err.to_string().contains("Invalid commit SHA"));
+}
+
+#[test]
+fn test_fetch_options_builder() {
+    let options = FetchOptions::builder()
+        .checks(true)
+        .job_logs(4000)
+        .graphql(true)
+        .follow_refs(2, 10)
+        .build()
+        .unwrap();
+    assert!(options.checks && options.graphql);
+    assert_eq!(options.job_log_limit, Some(4000));
+    let follow = options.follow_refs.unwrap();
+    assert_eq!((follow.depth, follow.max_items), (2, 10));
+    assert!(options.diff.is_none());
+
+    let err = FetchOptions::builder().job_logs(4000).build().unwrap_err();
+    assert!(err.to_string().contains("require checks"));
+    assert!(FetchOptions::builder().checks(true).job_logs(100).build().is_err());
+    assert!(FetchOptions::builder().follow_refs(0, 10).build().is_err());
+    assert!(FetchOptions::builder().follow_refs(1, 0).build().is_err());
+}
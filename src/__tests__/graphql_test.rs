@@ -0,0 +1,252 @@
+use super::fixtures::target;
+use super::mock_server::{ok, MockServer};
+use crate::backend::NativeBackend;
+use crate::gh::{fetch_context, to_timeline_event, FetchOptions, TargetType};
+use crate::graphql::{fetch_item, fetch_review_threads, to_rest_event};
+use serde_json::json;
+
+#[test]
+fn test_to_rest_event_labeled() {
+    let event = to_rest_event(json!({
+        "__typename": "LabeledEvent",
+        "actor": {"login": "alice"},
+        "createdAt": "2024-01-01T00:00:00Z",
+        "label": {"name": "bug"}
+    }));
+    assert_eq!(
+        event,
+        json!({
+            "event": "labeled",
+            "actor": {"login": "alice"},
+            "created_at": "2024-01-01T00:00:00Z",
+            "label": {"name": "bug"}
+        })
+    );
+}
+
+#[test]
+fn test_to_rest_event_names() {
+    let name = |typename: &str| to_rest_event(json!({ "__typename": typename }))["event"].clone();
+    assert_eq!(name("RenamedTitleEvent"), "renamed");
+    assert_eq!(name("CrossReferencedEvent"), "cross-referenced");
+    assert_eq!(name("ReviewRequestedEvent"), "review_requested");
+    assert_eq!(name("ReviewRequestRemovedEvent"), "review_request_removed");
+    assert_eq!(name("HeadRefForcePushedEvent"), "head_ref_force_pushed");
+}
+
+#[test]
+fn test_to_rest_event_ghost_actor_and_reviewer() {
+    let events: Vec<serde_json::Value> = [
+        json!({
+            "__typename": "LabeledEvent",
+            "actor": {"login": null},
+            "createdAt": "2024-01-01T00:00:00Z",
+            "label": {"name": "bug"}
+        }),
+        json!({
+            "__typename": "ReviewRequestedEvent",
+            "actor": null,
+            "createdAt": "2024-01-02T00:00:00Z",
+            "requestedReviewer": null
+        }),
+        json!({
+            "__typename": "ReviewRequestRemovedEvent",
+            "actor": {"login": "alice"},
+            "createdAt": "2024-01-03T00:00:00Z",
+            "requestedReviewer": {"name": "core"}
+        }),
+    ]
+    .into_iter()
+    .map(|node| {
+        let event = to_timeline_event(serde_json::from_value(to_rest_event(node)).unwrap());
+        serde_json::to_value(event).unwrap()
+    })
+    .collect();
+
+    assert_eq!(events[0]["actor"], "unknown");
+    assert_eq!(events[0]["label"], "bug");
+    assert_eq!(events[1]["actor"], "unknown");
+    assert_eq!(events[1]["reviewer"], "unknown");
+    assert_eq!(events[2]["event"], "review_request_removed");
+    assert_eq!(events[2]["reviewer"], "team core");
+}
+
+#[test]
+fn test_to_rest_event_closed_and_cross_referenced() {
+    let closed = to_rest_event(json!({
+        "__typename": "ClosedEvent",
+        "actor": {"login": "bob"},
+        "createdAt": "2024-01-02T00:00:00Z",
+        "stateReason": "NOT_PLANNED",
+        "closer": {"oid": "abc123"}
+    }));
+    assert_eq!(closed["commit_id"], "abc123");
+    assert_eq!(closed["state_reason"], "not_planned");
+
+    let referenced = to_rest_event(json!({
+        "__typename": "CrossReferencedEvent",
+        "actor": {"login": "carol"},
+        "createdAt": "2024-01-03T00:00:00Z",
+        "source": {
+            "number": 42,
+            "title": "Related",
+            "url": "https://github.com/other/repo/pull/42",
+            "repository": {"nameWithOwner": "other/repo"}
+        }
+    }));
+    assert_eq!(referenced["source"]["issue"]["number"], 42);
+    assert_eq!(
+        referenced["source"]["issue"]["repository"]["full_name"],
+        "other/repo"
+    );
+}
+
+const FIRST_PAGE: &str = r#"{"data": {"repository": {"issue": {
+    "title": "Flaky test",
+    "body": "Fails sometimes.",
+    "url": "https://github.com/owner/repo/issues/3",
+    "number": 3,
+    "author": {"login": "alice"},
+    "state": "OPEN",
+    "stateReason": null,
+    "locked": true,
+    "milestone": null,
+    "createdAt": "2024-01-01T00:00:00Z",
+    "updatedAt": "2024-01-02T00:00:00Z",
+    "closedAt": null,
+    "comments": {
+        "pageInfo": {"hasNextPage": true, "endCursor": "c1"},
+        "nodes": [{"author": {"login": "bob"}, "body": "first", "createdAt": "2024-01-01T01:00:00Z"}]
+    },
+    "labels": {"pageInfo": {"hasNextPage": false, "endCursor": null}, "nodes": [{"name": "ci"}]},
+    "assignees": {"pageInfo": {"hasNextPage": false, "endCursor": null}, "nodes": []},
+    "timelineItems": {
+        "pageInfo": {"hasNextPage": false, "endCursor": null},
+        "nodes": [{"__typename": "LockedEvent", "actor": {"login": "alice"},
+                   "createdAt": "2024-01-02T00:00:00Z", "lockReason": "TOO_HEATED"}]
+    }
+}}}}"#;
+
+const SECOND_PAGE: &str = r#"{"data": {"repository": {"issue": {
+    "comments": {
+        "pageInfo": {"hasNextPage": false, "endCursor": "c2"},
+        "nodes": [{"author": null, "body": "second", "createdAt": "2024-01-01T02:00:00Z"}]
+    }
+}}}}"#;
+
+#[test]
+fn test_fetch_item_follows_connection_pages() {
+    let server = MockServer::start(vec![("/graphql", ok(FIRST_PAGE)), ("/graphql", ok(SECOND_PAGE))]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let item = fetch_item(&backend, &target(TargetType::Issue, 3)).unwrap();
+    assert_eq!(item.response.title, "Flaky test");
+    assert_eq!(item.response.comments.len(), 2);
+    assert_eq!(item.response.comments[1].body, "second");
+    assert_eq!(item.response.labels[0].name, "ci");
+    assert_eq!(item.response.locked, Some(true));
+    assert_eq!(item.events[0]["event"], "locked");
+    assert_eq!(item.events[0]["lock_reason"], "too_heated");
+    assert!(item.review_threads.is_empty());
+    assert_eq!(server.paths(), vec!["/graphql", "/graphql"]);
+}
+
+#[test]
+fn test_fetch_context_graphql_option() {
+    let server = MockServer::start(vec![("/graphql", ok(FIRST_PAGE)), ("/graphql", ok(SECOND_PAGE))]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let options = FetchOptions {
+        graphql: true,
+        ..FetchOptions::default()
+    };
+
+    let context = fetch_context(&backend, &target(TargetType::Issue, 3), &options).unwrap();
+    assert_eq!(context.metadata.state.as_deref(), Some("OPEN"));
+    assert_eq!(context.metadata.labels, vec!["ci"]);
+    assert!(context.metadata.locked);
+    assert_eq!(context.comments[1].author, "ghost");
+    // Only GraphQL requests; no REST timeline call.
+    assert!(server.paths().iter().all(|p| p == "/graphql"));
+}
+
+const DISCUSSION: &str = r#"{"data": {"repository": {"discussion": {
+    "title": "Split the crate?", "body": "Thoughts?", "number": 3,
+    "url": "https://github.com/owner/repo/discussions/3",
+    "author": {"login": "alice"}, "closed": true, "stateReason": "RESOLVED", "locked": false,
+    "category": {"name": "Ideas"},
+    "createdAt": "2024-01-01T00:00:00Z", "updatedAt": "2024-01-02T00:00:00Z", "closedAt": null,
+    "comments": {
+        "pageInfo": {"hasNextPage": false, "endCursor": null},
+        "nodes": [
+            {"id": "DC_1", "author": {"login": "bob"}, "body": "Yes", "createdAt": "2024-01-01T01:00:00Z",
+             "isAnswer": false,
+             "replies": {"pageInfo": {"hasNextPage": true, "endCursor": "r1"},
+                         "nodes": [{"author": {"login": "carol"}, "body": "Why?", "createdAt": "2024-01-01T02:00:00Z"}]}},
+            {"id": "DC_2", "author": {"login": "dave"}, "body": "After 1.0", "createdAt": "2024-01-01T03:00:00Z",
+             "isAnswer": true,
+             "replies": {"pageInfo": {"hasNextPage": false, "endCursor": null}, "nodes": []}}
+        ]
+    },
+    "labels": {"pageInfo": {"hasNextPage": false, "endCursor": null}, "nodes": [{"name": "design"}]}
+}}}}"#;
+
+const MORE_REPLIES: &str = r#"{"data": {"node": {"replies": {
+    "pageInfo": {"hasNextPage": false, "endCursor": "r2"},
+    "nodes": [{"author": {"login": "bob"}, "body": "Build times.", "createdAt": "2024-01-01T04:00:00Z"}]
+}}}}"#;
+
+#[test]
+fn test_fetch_context_discussion_with_replies() {
+    let server = MockServer::start(vec![("/graphql", ok(DISCUSSION)), ("/graphql", ok(MORE_REPLIES))]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let target = target(TargetType::Discussion, 3);
+
+    let context = fetch_context(&backend, &target, &FetchOptions::default()).unwrap();
+    assert_eq!(context.metadata.r#type, "discussion");
+    assert_eq!(context.metadata.state.as_deref(), Some("CLOSED"));
+    assert_eq!(context.metadata.state_reason.as_deref(), Some("RESOLVED"));
+    assert_eq!(context.metadata.category.as_deref(), Some("Ideas"));
+    assert_eq!(context.metadata.labels, vec!["design"]);
+    assert!(!context.metadata.locked);
+    assert!(context.commits.is_none() && context.reviews.is_none());
+
+    let replies: Vec<&str> = context.comments[0]
+        .replies
+        .iter()
+        .map(|r| r.body.as_str())
+        .collect();
+    assert_eq!(replies, vec!["Why?", "Build times."]);
+    assert!(!context.comments[0].is_answer);
+    assert!(context.comments[1].is_answer);
+    assert!(server.paths().iter().all(|p| p == "/graphql"));
+}
+
+const THREADS: &str = r#"{"data": {"repository": {"pullRequest": {"reviewThreads": {
+    "pageInfo": {"hasNextPage": false, "endCursor": null},
+    "nodes": [{
+        "id": "PRRT_1", "path": "src/lib.rs", "line": 7, "startLine": null,
+        "originalLine": 7, "originalStartLine": null, "isResolved": false, "isOutdated": false,
+        "comments": {"pageInfo": {"hasNextPage": true, "endCursor": "c1"},
+            "nodes": [{"author": {"login": "bob"}, "body": "Why unwrap?",
+                "createdAt": "2024-01-01T01:00:00Z", "diffHunk": "@@ -7 +7 @@"}]}
+    }]
+}}}}}"#;
+
+const MORE_THREAD_COMMENTS: &str = r#"{"data": {"node": {"comments": {
+    "pageInfo": {"hasNextPage": false, "endCursor": "c2"},
+    "nodes": [{"author": {"login": "alice"}, "body": "Fixed.",
+        "createdAt": "2024-01-01T02:00:00Z", "diffHunk": "@@ -7 +7 @@"}]
+}}}}"#;
+
+#[test]
+fn test_fetch_review_threads_follows_comment_pages() {
+    let server = MockServer::start(vec![("/graphql", ok(THREADS)), ("/graphql", ok(MORE_THREAD_COMMENTS))]);
+    let backend = NativeBackend::new(&server.url, "secret");
+    let target = target(TargetType::Pr, 3);
+
+    let threads = fetch_review_threads(&backend, &target).unwrap();
+    assert_eq!(threads.len(), 1);
+    let comments: Vec<&str> = threads[0].comments.nodes.iter().map(|c| c.body.as_str()).collect();
+    assert_eq!(comments, vec!["Why unwrap?", "Fixed."]);
+    assert_eq!(server.paths(), vec!["/graphql", "/graphql"]);
+}
@@ -0,0 +1,77 @@
+use crate::jobs::run_ordered;
+use anyhow::anyhow;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_run_ordered_hands_results_over_in_item_order() {
+    let items: Vec<u64> = (1..=12).collect();
+    let running = AtomicUsize::new(0);
+    let most_running = AtomicUsize::new(0);
+    let mut handled = Vec::new();
+
+    run_ordered(
+        &items,
+        4,
+        |&n| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now, Ordering::SeqCst);
+            // Early items finish last
+            thread::sleep(Duration::from_millis(2 * (13 - n)));
+            running.fetch_sub(1, Ordering::SeqCst);
+            n * 10
+        },
+        |&n, result| {
+            handled.push((n, result));
+            Ok(())
+        },
+    )
+    .unwrap();
+
+    let expected: Vec<(u64, u64)> = items.iter().map(|&n| (n, n * 10)).collect();
+    assert_eq!(handled, expected);
+    let most_running = most_running.load(Ordering::SeqCst);
+    assert!(most_running > 1 && most_running <= 4, "{}", most_running);
+}
+
+#[test]
+fn test_run_ordered_stops_after_handler_error() {
+    let items: Vec<u64> = (1..=50).collect();
+    let started = AtomicUsize::new(0);
+    let mut handled = Vec::new();
+
+    let err = run_ordered(
+        &items,
+        2,
+        |&n| {
+            started.fetch_add(1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(1));
+            n
+        },
+        |&n, _| {
+            if n == 3 {
+                return Err(anyhow!("disk full"));
+            }
+            handled.push(n);
+            Ok(())
+        },
+    )
+    .unwrap_err();
+
+    assert_eq!(err.to_string(), "disk full");
+    assert_eq!(handled, vec![1, 2]);
+    assert!(started.load(Ordering::SeqCst) < items.len());
+}
+
+#[test]
+fn test_run_ordered_with_one_job_is_sequential() {
+    let items = ["a", "b", "c"];
+    let mut handled = Vec::new();
+    run_ordered(&items, 1, |s| s.to_uppercase(), |_, upper| {
+        handled.push(upper);
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(handled, vec!["A", "B", "C"]);
+}
@@ -0,0 +1,43 @@
+use crate::logs::excerpt;
+
+#[test]
+fn test_excerpt_keeps_error_context_and_strips_timestamps() {
+    let mut log = String::new();
+    for i in 0..50 {
+        log.push_str(&format!("2024-05-01T10:00:{:02}.1234567Z step output {}\n", i % 60, i));
+    }
+    log.push_str("2024-05-01T10:01:00.0000000Z thread 'main' panicked at src/lib.rs:3:5\n");
+    log.push_str("2024-05-01T10:01:01.0000000Z ##[error]Process completed with exit code 101.\n");
+
+    let out = excerpt(&log, 4000);
+    assert!(out.starts_with("..."));
+    assert!(out.contains("step output 47"));
+    assert!(!out.contains("step output 10\n"));
+    assert!(out.contains("thread 'main' panicked at src/lib.rs:3:5"));
+    assert!(out.ends_with("##[error]Process completed with exit code 101."));
+    assert!(!out.contains("2024-05-01T"));
+}
+
+#[test]
+fn test_excerpt_falls_back_to_tail_without_markers() {
+    let log = "one\ntwo\nthree\n";
+    assert_eq!(excerpt(log, 4000), "one\ntwo\nthree");
+}
+
+#[test]
+fn test_excerpt_drops_group_markers() {
+    let log = "##[group]Run cargo test\ncargo test\n##[endgroup]\nerror: could not compile\n";
+    let out = excerpt(log, 4000);
+    assert!(!out.contains("##[group]"));
+    assert!(!out.contains("##[endgroup]"));
+    assert!(out.contains("error: could not compile"));
+}
+
+#[test]
+fn test_excerpt_is_size_capped_keeping_the_end() {
+    let log: String = (0..1000).map(|i| format!("error: failure number {}\n", i)).collect();
+    let out = excerpt(&log, 512);
+    assert!(out.len() <= 512);
+    assert!(out.starts_with("... [truncated]\n"));
+    assert!(out.ends_with("error: failure number 999"));
+}
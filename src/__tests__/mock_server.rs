@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tiny_http::{Header, Response, Server};
+
+#[derive(Clone)]
+pub struct Route {
+    pub status: u16,
+    pub body: String,
+    pub link: Option<String>,
+    pub etag: Option<String>,
+    pub retry_after: Option<String>,
+}
+
+pub fn ok(body: &str) -> Route {
+    Route {
+        status: 200,
+        body: body.to_string(),
+        link: None,
+        etag: None,
+        retry_after: None,
+    }
+}
+
+pub struct RecordedRequest {
+    pub path: String,
+    pub authorization: Option<String>,
+    pub if_none_match: Option<String>,
+}
+
+// Serves canned responses keyed by request path (including the query) and
+// records each request. Repeating a path queues responses, the last one
+// being served from then on. `{url}` in a Link header is replaced with the
+// server's base URL.
+pub struct MockServer {
+    server: Arc<Server>,
+    pub url: String,
+    pub requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    pub fn start(routes: Vec<(&str, Route)>) -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let port = server.server_addr().to_ip().unwrap().port();
+        let url = format!("http://127.0.0.1:{}", port);
+        let mut routes_by_path: HashMap<String, VecDeque<Route>> = HashMap::new();
+        for (path, route) in routes {
+            routes_by_path
+                .entry(path.to_string())
+                .or_default()
+                .push_back(route);
+        }
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let handle = {
+            let server = Arc::clone(&server);
+            let requests = Arc::clone(&requests);
+            let url = url.clone();
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    let header = |name: &str| {
+                        request
+                            .headers()
+                            .iter()
+                            .find(|h| h.field.as_str().as_str().eq_ignore_ascii_case(name))
+                            .map(|h| h.value.to_string())
+                    };
+                    requests.lock().unwrap().push(RecordedRequest {
+                        path: request.url().to_string(),
+                        authorization: header("Authorization"),
+                        if_none_match: header("If-None-Match"),
+                    });
+
+                    let route = routes_by_path.get_mut(request.url()).and_then(|queue| {
+                        if queue.len() > 1 {
+                            queue.pop_front()
+                        } else {
+                            queue.front().cloned()
+                        }
+                    });
+                    let response = match route {
+                        Some(route) => {
+                            let mut response = Response::from_string(route.body)
+                                .with_status_code(route.status);
+                            if let Some(link) = &route.link {
+                                let link = link.replace("{url}", &url);
+                                response = response
+                                    .with_header(Header::from_bytes("Link", link).unwrap());
+                            }
+                            if let Some(etag) = &route.etag {
+                                response = response
+                                    .with_header(Header::from_bytes("ETag", etag.as_str()).unwrap());
+                            }
+                            if let Some(retry_after) = &route.retry_after {
+                                response = response.with_header(
+                                    Header::from_bytes("Retry-After", retry_after.as_str()).unwrap(),
+                                );
+                            }
+                            response
+                        }
+                        None => Response::from_string(r#"{"message":"Not Found"}"#)
+                            .with_status_code(404),
+                    };
+                    let _ = request.respond(response);
+                }
+            })
+        };
+
+        MockServer {
+            server,
+            url,
+            requests,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn paths(&self) -> Vec<String> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| r.path.clone())
+            .collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
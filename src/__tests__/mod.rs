@@ -1,2 +1,20 @@
+mod fixtures;
+mod mock_server;
+
 mod gh_test;
-mod bulk_test;
+mod diff_test;
+mod format_test;
+mod logs_test;
+mod backend_test;
+mod graphql_test;
+mod refs_test;
+mod attachments_test;
+mod cache_test;
+mod batch_test;
+mod sync_test;
+mod checkpoint_test;
+mod report_test;
+mod error_test;
+mod retry_test;
+mod jobs_test;
+mod runner_test;
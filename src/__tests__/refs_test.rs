@@ -0,0 +1,167 @@
+use super::fixtures::target;
+use super::mock_server::{ok, MockServer, Route};
+use crate::backend::NativeBackend;
+use crate::error::ErrorKind;
+use crate::format::to_markdown;
+use crate::gh::{fetch_context, FetchOptions, TargetType};
+use crate::refs::{find_references, FollowRefs, ItemRef};
+
+fn item(owner: &str, repo: &str, number: u64, kind: Option<TargetType>) -> ItemRef {
+    ItemRef {
+        owner: owner.to_string(),
+        repo: repo.to_string(),
+        number,
+        kind,
+    }
+}
+
+fn issue(number: u64, body: &str) -> String {
+    serde_json::json!({
+        "number": number,
+        "title": format!("Issue {}", number),
+        "body": body,
+        "html_url": format!("https://github.com/owner/repo/issues/{}", number),
+        "user": {"login": "alice"},
+        "state": "open"
+    })
+    .to_string()
+}
+
+fn follow_options(depth: u32, max_items: usize) -> FetchOptions {
+    FetchOptions {
+        follow_refs: Some(FollowRefs { depth, max_items }),
+        ..FetchOptions::default()
+    }
+}
+
+#[test]
+fn test_find_references_shorthand_and_urls() {
+    let text = "Fixes #12, see other-org/lib.rs#3.\n\
+                Also https://github.com/owner/repo/pull/40#issuecomment-1 and \
+                (https://ghe.example.com/owner/repo/issues/9).";
+    let refs = find_references(text, "github.com", "owner", "repo");
+    assert_eq!(
+        refs,
+        vec![
+            item("owner", "repo", 12, None),
+            item("other-org", "lib.rs", 3, None),
+            item("owner", "repo", 40, Some(TargetType::Pr)),
+        ]
+    );
+}
+
+#[test]
+fn test_find_references_skips_code_and_non_references() {
+    let text = "Color #fff, entity &#123; and a#5 are not references.\n\
+                Commits like https://github.com/owner/repo/commit/abcdef1 are not followed.\n\
+                ```\n\
+                error at #77\n\
+                ```\n\
+                But #8 is.";
+    let refs = find_references(text, "github.com", "owner", "repo");
+    assert_eq!(refs, vec![item("owner", "repo", 8, None)]);
+}
+
+#[test]
+fn test_follow_refs_stops_at_cycles_and_depth() {
+    let one = issue(1, "Duplicate of #2");
+    let two = issue(2, "Back to #1, and see #3");
+    let three = issue(3, "See #4");
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/1", ok(&one)),
+        ("/repos/owner/repo/issues/2", ok(&two)),
+        ("/repos/owner/repo/issues/3", ok(&three)),
+        ("/repos/owner/repo/issues/1/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/2/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/3/comments?per_page=100", ok("[]")),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(&backend, &target(TargetType::Issue, 1), &follow_options(2, 10)).unwrap();
+
+    let found: Vec<(u64, u32, &str)> = context
+        .references
+        .iter()
+        .map(|r| {
+            (
+                r.context.metadata.number.unwrap(),
+                r.depth,
+                r.referenced_from.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(found, vec![(2, 1, "owner/repo#1"), (3, 2, "owner/repo#2")]);
+    assert!(context.references.iter().all(|r| r.context.references.is_empty()));
+    assert!(!server.paths().iter().any(|p| p.contains("/issues/4")));
+
+    let md = to_markdown(&context);
+    assert!(md.contains("## Referenced Issues and PRs\n\n- [owner/repo#2]"));
+    assert!(md.contains("\n## Issue 2\n"));
+    assert!(md.contains("\n### Body\n"));
+}
+
+#[test]
+fn test_follow_refs_respects_item_cap() {
+    let one = issue(1, "Related: #2 #3 #4");
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/1", ok(&one)),
+        ("/repos/owner/repo/issues/2", ok(&issue(2, ""))),
+        ("/repos/owner/repo/issues/3", ok(&issue(3, ""))),
+        ("/repos/owner/repo/issues/1/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/2/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/3/comments?per_page=100", ok("[]")),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(&backend, &target(TargetType::Issue, 1), &follow_options(1, 2)).unwrap();
+
+    let numbers: Vec<u64> = context
+        .references
+        .iter()
+        .map(|r| r.context.metadata.number.unwrap())
+        .collect();
+    assert_eq!(numbers, vec![2, 3]);
+}
+
+fn failing(status: u16) -> Route {
+    Route {
+        status,
+        body: r#"{"message":"failed"}"#.to_string(),
+        link: None,
+        etag: None,
+        retry_after: None,
+    }
+}
+
+#[test]
+fn test_follow_refs_skips_failed_items_but_not_auth_failures() {
+    // #2 is gone and #3 fails; #4 is still followed.
+    let one = issue(1, "Related: #2 #3 #4");
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/1", ok(&one)),
+        ("/repos/owner/repo/issues/3", failing(500)),
+        ("/repos/owner/repo/issues/4", ok(&issue(4, ""))),
+        ("/repos/owner/repo/issues/1/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/4/comments?per_page=100", ok("[]")),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let context = fetch_context(&backend, &target(TargetType::Issue, 1), &follow_options(1, 10)).unwrap();
+    let numbers: Vec<u64> = context
+        .references
+        .iter()
+        .map(|r| r.context.metadata.number.unwrap())
+        .collect();
+    assert_eq!(numbers, vec![4]);
+
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo/issues/1", ok(&one)),
+        ("/repos/owner/repo/issues/1/comments?per_page=100", ok("[]")),
+        ("/repos/owner/repo/issues/3", failing(401)),
+    ]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let err = fetch_context(&backend, &target(TargetType::Issue, 1), &follow_options(1, 10)).unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::AuthRequired);
+    assert!(format!("{:#}", err).contains("owner/repo#3"));
+}
@@ -0,0 +1,38 @@
+use super::fixtures::temp_dir;
+use crate::error::GhError;
+use crate::report::{write, Failure, Outcome, REPORT_FILE};
+use anyhow::anyhow;
+use std::fs;
+use std::process::ExitCode;
+
+#[test]
+fn test_outcome_exit_codes() {
+    assert_eq!(Outcome::of(5, 0), Outcome::Complete);
+    assert_eq!(Outcome::of(0, 0), Outcome::Complete);
+    assert_eq!(Outcome::of(4, 1), Outcome::Partial);
+    assert_eq!(Outcome::of(0, 2), Outcome::Failed);
+
+    let not_found = |n| Failure::new(n, &GhError::NotFound("Not Found".to_string()).into());
+    let other = Failure::new(3, &anyhow!("disk full"));
+    assert_eq!(Outcome::Partial.exit_code(&[not_found(1)]), ExitCode::from(3));
+    // All failed for the same cause: exit as that cause
+    assert_eq!(Outcome::Failed.exit_code(&[not_found(1), not_found(2)]), ExitCode::from(4));
+    assert_eq!(Outcome::Failed.exit_code(&[not_found(1), other]), ExitCode::FAILURE);
+}
+
+#[test]
+fn test_write_report_and_remove_stale_one() {
+    let dir = temp_dir("report");
+
+    let failures = vec![Failure::new(7, &GhError::NotFound("failed with status 404: Not Found".to_string()).into())];
+    let path = write(&dir, &failures).unwrap().unwrap();
+    assert_eq!(path, dir.join(REPORT_FILE));
+    let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!([{"number": 7, "class": "not_found", "message": "failed with status 404: Not Found"}])
+    );
+
+    assert!(write(&dir, &[]).unwrap().is_none());
+    assert!(!path.exists());
+}
@@ -0,0 +1,123 @@
+use super::mock_server::{ok, MockServer, Route};
+use crate::backend::{Backend, NativeBackend, RateLimit};
+use crate::error::{ErrorKind, GhError};
+use crate::retry::{delay, RetryBackend, RetryPolicy};
+use anyhow::anyhow;
+use std::time::Duration;
+
+fn quick_policy() -> RetryPolicy {
+    RetryPolicy {
+        retries: 2,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(4),
+        max_wait: Duration::from_secs(5),
+        secondary_delay: Duration::from_millis(1),
+    }
+}
+
+fn failing(status: u16, retry_after: Option<&str>) -> Route {
+    Route {
+        status,
+        retry_after: retry_after.map(str::to_string),
+        ..ok(r#"{"message":"try again"}"#)
+    }
+}
+
+#[test]
+fn test_retry_delay_from_headers() {
+    let now = 1_000;
+    let quota = RateLimit {
+        limit: Some(5000),
+        remaining: Some(0),
+        reset: Some(1_030),
+        retry_after: None,
+    };
+    assert_eq!(quota.retry_delay(now), Some(Duration::from_secs(30)));
+
+    let secondary = RateLimit {
+        retry_after: Some(60),
+        ..quota
+    };
+    assert_eq!(secondary.retry_delay(now), Some(Duration::from_secs(60)));
+
+    let remaining = RateLimit {
+        remaining: Some(10),
+        ..quota
+    };
+    assert_eq!(remaining.retry_delay(now), None);
+}
+
+#[test]
+fn test_pace_delay_spreads_remaining_quota() {
+    let now = 1_000;
+    let quota = |remaining| RateLimit {
+        limit: Some(5000),
+        remaining: Some(remaining),
+        reset: Some(1_100),
+        retry_after: None,
+    };
+    assert_eq!(quota(1000).pace_delay(now), None);
+    assert_eq!(quota(50).pace_delay(now), Some(Duration::from_secs(2)));
+    assert_eq!(quota(0).pace_delay(now), Some(Duration::from_secs(100)));
+    assert_eq!(RateLimit::default().pace_delay(now), None);
+}
+
+#[test]
+fn test_delay_by_error_kind() {
+    let policy = RetryPolicy::default();
+    let rate_limited = |retry_after| {
+        anyhow::Error::new(GhError::RateLimited {
+            message: "API rate limit exceeded".to_string(),
+            retry_after,
+        })
+    };
+
+    assert_eq!(
+        delay(&rate_limited(Some(Duration::from_secs(42))), 1, &policy),
+        Some(Duration::from_secs(42))
+    );
+    assert_eq!(delay(&rate_limited(None), 1, &policy), Some(Duration::from_secs(60)));
+    // Resets too far ahead to wait for
+    assert_eq!(delay(&rate_limited(Some(Duration::from_secs(3600))), 1, &policy), None);
+    assert_eq!(delay(&rate_limited(None), 4, &policy), None);
+
+    let network = anyhow::Error::new(GhError::Network("connection reset".to_string()));
+    let wait = delay(&network, 3, &policy).unwrap();
+    assert!(wait >= Duration::from_secs(2) && wait <= Duration::from_secs(4), "{:?}", wait);
+
+    let not_found = anyhow::Error::new(GhError::NotFound("Not Found".to_string()));
+    assert_eq!(delay(&not_found, 1, &policy), None);
+    assert_eq!(delay(&anyhow!("unexpected"), 1, &policy), None);
+}
+
+#[test]
+fn test_retry_backend_recovers_from_transient_failures() {
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo", failing(503, None)),
+        ("/repos/owner/repo", failing(429, Some("0"))),
+        ("/repos/owner/repo", ok(r#"{"name":"repo"}"#)),
+    ]);
+    let backend = RetryBackend::new(Box::new(NativeBackend::new(&server.url, "secret")), quick_policy());
+
+    let value = backend.rest("repos/owner/repo").unwrap();
+
+    assert_eq!(value["name"], "repo");
+    assert_eq!(server.paths().len(), 3);
+}
+
+#[test]
+fn test_retry_backend_gives_up() {
+    let server = MockServer::start(vec![
+        ("/repos/owner/repo", failing(502, None)),
+        ("/repos/owner/missing", failing(404, None)),
+    ]);
+    let backend = RetryBackend::new(Box::new(NativeBackend::new(&server.url, "secret")), quick_policy());
+
+    let err = backend.rest("repos/owner/repo").unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::Network);
+    assert_eq!(server.paths().len(), 3);
+
+    let err = backend.rest("repos/owner/missing").unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::NotFound);
+    assert_eq!(server.paths().len(), 4);
+}
@@ -0,0 +1,102 @@
+use super::fixtures::temp_dir;
+use crate::backend::{Backend, GhBackend};
+use crate::error::{ErrorKind, GhError};
+use crate::format::to_markdown;
+use crate::gh::{fetch_context, parse_target, FetchOptions};
+use crate::runner::{Cassette, CommandOutput, CommandRunner, RecordingRunner, ReplayRunner};
+use std::io;
+use std::path::PathBuf;
+
+fn output(code: i32, stdout: &str, stderr: &str) -> CommandOutput {
+    CommandOutput {
+        code: Some(code),
+        stdout: stdout.to_string(),
+        stderr: stderr.to_string(),
+    }
+}
+
+// Answers like `gh` would for issue #7 of owner/repo.
+struct FakeGh;
+
+impl CommandRunner for FakeGh {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        assert_eq!(program, "gh");
+        Ok(match args {
+            ["issue", "view", "7", ..] => output(
+                0,
+                r#"{"title": "Crash on start", "body": "It crashes.", "url": "https://github.com/owner/repo/issues/7",
+                    "number": 7, "state": "OPEN", "author": {"login": "alice"},
+                    "comments": [{"author": {"login": "bob"}, "body": "Same here", "createdAt": "2024-01-02T00:00:00Z"}]}"#,
+                "",
+            ),
+            ["api", "repos/owner/repo/issues/7/timeline?per_page=100", ..] => output(
+                0,
+                r#"[{"event": "labeled", "actor": {"login": "carol"}, "created_at": "2024-01-03T00:00:00Z", "label": {"name": "bug"}}]"#,
+                "",
+            ),
+            _ => output(1, "", "gh: Not Found (HTTP 404)"),
+        })
+    }
+}
+
+#[test]
+fn test_recorded_cassette_replays_full_pipeline() {
+    let dir = temp_dir("runner-pipeline");
+    let cassette = dir.join("issue-7.json");
+    let target = parse_target("https://github.com/owner/repo/issues/7", false, false).unwrap();
+
+    let recording = GhBackend::with_runner(
+        "github.com",
+        Box::new(RecordingRunner::new(Box::new(FakeGh), &cassette).unwrap()),
+    );
+    let recorded = to_markdown(&fetch_context(&recording, &target, &FetchOptions::default()).unwrap());
+    assert_eq!(Cassette::load(&cassette).unwrap().interactions.len(), 2);
+
+    let replaying = GhBackend::with_runner("github.com", Box::new(ReplayRunner::load(&cassette).unwrap()));
+    let replayed = to_markdown(&fetch_context(&replaying, &target, &FetchOptions::default()).unwrap());
+
+    assert_eq!(replayed, recorded);
+    assert!(replayed.contains("# Crash on start"));
+    assert!(replayed.contains("Same here"));
+    assert!(replayed.contains("added label `bug`"));
+}
+
+#[test]
+fn test_replay_serves_failures_and_rejects_unknown_commands() {
+    let dir = temp_dir("runner-failures");
+    let cassette = dir.join("cassette.json");
+    let recording = GhBackend::with_runner(
+        "github.com",
+        Box::new(RecordingRunner::new(Box::new(FakeGh), &cassette).unwrap()),
+    );
+    assert!(recording.rest_text("repos/owner/repo/issues/8", "text/plain").is_err());
+
+    let replaying = GhBackend::with_runner("github.com", Box::new(ReplayRunner::load(&cassette).unwrap()));
+    let err = replaying.rest_text("repos/owner/repo/issues/8", "text/plain").unwrap_err();
+    assert_eq!(ErrorKind::of(&err), ErrorKind::NotFound);
+
+    let err = replaying.rest_text("repos/owner/repo/issues/9", "text/plain").unwrap_err();
+    assert!(format!("{:#}", err).contains("no recorded output for 'gh api repos/owner/repo/issues/9"));
+    // Unlike a missing `gh`, a cassette miss has no typed cause
+    assert!(GhError::find(&err).is_none());
+}
+
+#[test]
+fn test_replay_serves_repeated_commands_in_order() {
+    let path = PathBuf::from("cassette.json");
+    let cassette: Cassette = serde_json::from_value(serde_json::json!({
+        "interactions": [
+            {"program": "gh", "args": ["api", "rate_limit"], "code": 1, "stdout": "", "stderr": "HTTP 502"},
+            {"program": "gh", "args": ["api", "rate_limit"], "code": 0, "stdout": "first", "stderr": ""},
+            {"program": "gh", "args": ["api", "rate_limit"], "code": 0, "stdout": "second", "stderr": ""}
+        ]
+    }))
+    .unwrap();
+    let runner = ReplayRunner::new(cassette, &path);
+
+    let stdout = |runner: &ReplayRunner| runner.run("gh", &["api", "rate_limit"]).unwrap();
+    assert!(!stdout(&runner).success());
+    assert_eq!(stdout(&runner).stdout, "first");
+    assert_eq!(stdout(&runner).stdout, "second");
+    assert_eq!(stdout(&runner).stdout, "second");
+}
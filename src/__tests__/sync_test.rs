@@ -0,0 +1,185 @@
+use super::fixtures::temp_dir;
+use super::mock_server::{ok, MockServer};
+use crate::backend::NativeBackend;
+use crate::sync::{
+    last_update, list_issues, load_state, plan, save_state, set_options, since, SyncState, SyncedIssue,
+};
+use std::collections::BTreeMap;
+use std::fs;
+
+fn synced(updated_at: &str, file: &str) -> SyncedIssue {
+    SyncedIssue {
+        updated_at: updated_at.to_string(),
+        file: file.to_string(),
+    }
+}
+
+#[test]
+fn test_list_issues_skips_pull_requests() {
+    let page = r#"[
+        {"number": 1, "state": "open", "updated_at": "2024-01-01T00:00:00Z"},
+        {"number": 2, "state": "open", "updated_at": "2024-01-02T00:00:00Z", "pull_request": {"url": "x"}},
+        {"number": 3, "state": "open", "updated_at": "2024-01-03T00:00:00Z"}
+    ]"#;
+    let server = MockServer::start(vec![(
+        "/repos/owner/repo/issues?state=all&sort=created&direction=asc&per_page=100",
+        ok(page),
+    )]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let issues = list_issues(&backend, "owner", "repo", "all", None).unwrap();
+
+    let listed: Vec<(u64, &str)> = issues.iter().map(|i| (i.number, i.updated_at.as_str())).collect();
+    assert_eq!(
+        listed,
+        vec![(1, "2024-01-01T00:00:00Z"), (3, "2024-01-03T00:00:00Z")]
+    );
+}
+
+#[test]
+fn test_plan_fetches_changed_and_removes_unlisted() {
+    let dir = temp_dir("sync-plan");
+    for file in ["repo-issue-1.md", "repo-issue-2.md", "repo-issue-4.json", "repo-issue-5.md"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+    let state = SyncState {
+        repo: "owner/repo".to_string(),
+        state: "open".to_string(),
+        last_update: None,
+        options: String::new(),
+        issues: BTreeMap::from([
+            (1, synced("2024-01-01T00:00:00Z", "repo-issue-1.md")),
+            (2, synced("2024-01-02T00:00:00Z", "repo-issue-2.md")),
+            (3, synced("2024-01-03T00:00:00Z", "repo-issue-3.md")),
+            (4, synced("2024-01-04T00:00:00Z", "repo-issue-4.json")),
+            (5, synced("2024-01-05T00:00:00Z", "repo-issue-5.md")),
+        ]),
+    };
+    let listed = serde_json::from_value(serde_json::json!([
+        {"number": 1, "state": "open", "updated_at": "2024-01-01T00:00:00Z"},
+        {"number": 2, "state": "open", "updated_at": "2024-02-01T00:00:00Z"},
+        {"number": 3, "state": "open", "updated_at": "2024-01-03T00:00:00Z"},
+        {"number": 4, "state": "open", "updated_at": "2024-01-04T00:00:00Z"},
+        {"number": 6, "state": "open", "updated_at": "2024-01-06T00:00:00Z"}
+    ]))
+    .unwrap();
+
+    let plan = plan(&state, listed, true, "open", &dir, "md");
+
+    // 2 was updated, 3 lost its file, 4 was written as JSON, 6 is new
+    let fetch: Vec<u64> = plan.fetch.iter().map(|i| i.number).collect();
+    assert_eq!(fetch, vec![2, 3, 4, 6]);
+    assert_eq!(plan.unchanged, 1);
+    let remove: Vec<(u64, &str)> = plan.remove.iter().map(|(n, s)| (*n, s.file.as_str())).collect();
+    assert_eq!(remove, vec![(5, "repo-issue-5.md")]);
+}
+
+#[test]
+fn test_list_issues_since_lists_updates_in_any_state() {
+    let page = r#"[
+        {"number": 4, "state": "closed", "updated_at": "2024-02-01T00:00:00Z"},
+        {"number": 5, "state": "open", "updated_at": "2024-02-02T00:00:00Z", "pull_request": {"url": "x"}}
+    ]"#;
+    let server = MockServer::start(vec![(
+        "/repos/owner/repo/issues?state=all&since=2024-01-31T00:00:00Z&sort=updated&direction=asc&per_page=100",
+        ok(page),
+    )]);
+    let backend = NativeBackend::new(&server.url, "secret");
+
+    let issues = list_issues(&backend, "owner", "repo", "open", Some("2024-01-31T00:00:00Z")).unwrap();
+
+    assert_eq!(issues.len(), 1);
+    assert_eq!((issues[0].number, issues[0].state.as_str()), (4, "closed"));
+}
+
+#[test]
+fn test_incremental_plan_keeps_unlisted_and_removes_closed() {
+    let dir = temp_dir("sync-incremental");
+    for file in ["repo-issue-1.md", "repo-issue-2.md", "repo-issue-3.md"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+    let mut state = SyncState {
+        repo: "owner/repo".to_string(),
+        state: "open".to_string(),
+        last_update: Some("2024-01-03T00:00:00Z".to_string()),
+        options: String::new(),
+        issues: BTreeMap::from([
+            (1, synced("2024-01-01T00:00:00Z", "repo-issue-1.md")),
+            (2, synced("2024-01-02T00:00:00Z", "repo-issue-2.md")),
+            (3, synced("2024-01-03T00:00:00Z", "repo-issue-3.md")),
+        ]),
+    };
+    assert_eq!(since(&state, "open", &dir), Some("2024-01-03T00:00:00Z"));
+    assert_eq!(since(&state, "all", &dir), None);
+
+    let listed: Vec<_> = serde_json::from_value(serde_json::json!([
+        {"number": 2, "state": "closed", "updated_at": "2024-02-01T00:00:00Z"},
+        {"number": 3, "state": "open", "updated_at": "2024-01-03T00:00:00Z"},
+        {"number": 7, "state": "open", "updated_at": "2024-02-02T00:00:00Z"}
+    ]))
+    .unwrap();
+    assert_eq!(last_update(&state, &listed).as_deref(), Some("2024-02-02T00:00:00Z"));
+
+    let plan = plan(&state, listed, false, "open", &dir, "md");
+
+    let fetch: Vec<u64> = plan.fetch.iter().map(|i| i.number).collect();
+    assert_eq!(fetch, vec![7]);
+    assert_eq!(plan.unchanged, 2);
+    let remove: Vec<u64> = plan.remove.iter().map(|(n, _)| *n).collect();
+    assert_eq!(remove, vec![2]);
+
+    // A missing file needs a full listing to be fetched again
+    fs::remove_file(dir.join("repo-issue-1.md")).unwrap();
+    assert_eq!(since(&state, "open", &dir), None);
+    state.last_update = None;
+    assert_eq!(since(&state, "open", &dir), None);
+}
+
+#[test]
+fn test_changed_options_refetch_everything() {
+    let dir = temp_dir("sync-options");
+    fs::write(dir.join("repo-issue-1.md"), "").unwrap();
+    let mut state = SyncState {
+        repo: "owner/repo".to_string(),
+        state: "open".to_string(),
+        last_update: None,
+        options: "Markdown".to_string(),
+        issues: BTreeMap::from([(1, synced("2024-01-01T00:00:00Z", "repo-issue-1.md"))]),
+    };
+    let listed = || {
+        serde_json::from_value(serde_json::json!([
+            {"number": 1, "state": "open", "updated_at": "2024-01-01T00:00:00Z"}
+        ]))
+        .unwrap()
+    };
+
+    set_options(&mut state, "Markdown");
+    assert_eq!(plan(&state, listed(), true, "open", &dir, "md").unchanged, 1);
+
+    // Also after an interrupted sync with the new options
+    set_options(&mut state, "MarkdownChronological");
+    set_options(&mut state, "MarkdownChronological");
+    assert_eq!(state.options, "MarkdownChronological");
+    assert_eq!(plan(&state, listed(), true, "open", &dir, "md").fetch.len(), 1);
+}
+
+#[test]
+fn test_sync_state_round_trip_and_repo_check() {
+    let dir = temp_dir("sync-state");
+    assert!(load_state(&dir, "owner/repo").unwrap().is_none());
+
+    let state = SyncState {
+        repo: "owner/repo".to_string(),
+        state: "open".to_string(),
+        last_update: None,
+        options: String::new(),
+        issues: BTreeMap::from([(7, synced("2024-01-07T00:00:00Z", "repo-issue-7.md"))]),
+    };
+    save_state(&dir, &state).unwrap();
+
+    let loaded = load_state(&dir, "Owner/Repo").unwrap().unwrap();
+    assert_eq!(loaded.issues[&7].file, "repo-issue-7.md");
+
+    let err = load_state(&dir, "owner/other").unwrap_err();
+    assert!(err.to_string().contains("belongs to a sync of owner/repo"));
+}
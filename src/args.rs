@@ -7,6 +7,22 @@ pub enum OutputFormat {
     Md,
 }
 
+#[derive(Clone, ValueEnum, Debug)]
+pub enum Layout {
+    /// Comments first, then timeline events, each in its own section
+    Sections,
+    /// Comments and timeline events merged into one stream, oldest first
+    Chronological,
+}
+
+#[derive(Clone, ValueEnum, Debug)]
+pub enum BackendKind {
+    /// Shell out to the GitHub CLI (`gh`)
+    Gh,
+    /// Call the GitHub API directly, authenticating with GH_TOKEN or GITHUB_TOKEN
+    Native,
+}
+
 #[derive(Clone, ValueEnum, Debug)]
 pub enum IssueState {
     Open,
@@ -27,13 +43,29 @@ impl IssueState {
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
-    /// GitHub URL or shorthand (owner/repo#number), or repo (owner/repo) in bulk mode
+    /// GitHub URL or shorthand (owner/repo#number, owner/repo@sha), or repo (owner/repo) in bulk mode
     pub input: String,
 
     /// Output format
     #[arg(long, value_enum, default_value_t = OutputFormat::Md)]
     pub format: OutputFormat,
 
+    /// Markdown layout for comments and timeline events
+    #[arg(long, value_enum, default_value_t = Layout::Sections)]
+    pub layout: Layout,
+
+    /// How to talk to GitHub
+    #[arg(long, value_enum, env = "GH_CONTEXT_BACKEND", default_value_t = BackendKind::Gh)]
+    pub backend: BackendKind,
+
+    /// GitHub host for shorthand input, e.g. a GitHub Enterprise Server hostname
+    #[arg(long)]
+    pub hostname: Option<String>,
+
+    /// Fetch each issue/PR with a single GraphQL query (fewer requests for bulk and range runs)
+    #[arg(long)]
+    pub graphql: bool,
+
     /// Output to file
     #[arg(long)]
     pub out: Option<PathBuf>,
@@ -59,13 +91,25 @@ pub struct Cli {
     #[arg(long, value_enum, default_value_t = IssueState::Open)]
     pub state: IssueState,
 
-    /// Items per page for bulk mode (1-100)
-    #[arg(long, default_value_t = 30)]
-    pub per_page: u32,
+    /// Items per page for bulk mode (1-100) [default: 30]
+    #[arg(long)]
+    pub per_page: Option<u32>,
 
-    /// Number of pages to fetch in bulk mode
-    #[arg(long, default_value_t = 1)]
-    pub pages: u32,
+    /// Number of pages to fetch in bulk mode [default: 1]
+    #[arg(long)]
+    pub pages: Option<u32>,
+
+    /// Mirror all issues into the bulk output directory, regenerating only those updated since the last run
+    #[arg(long)]
+    pub sync: bool,
+
+    /// With --sync, list every issue instead of those updated since the last run, to also remove files of transferred or deleted issues
+    #[arg(long)]
+    pub sync_full: bool,
+
+    /// Continue an interrupted bulk or range run, skipping completed items and retrying failed ones
+    #[arg(long)]
+    pub resume: bool,
 
     /// Start PR number for range mode (inclusive)
     #[arg(long)]
@@ -74,4 +118,95 @@ pub struct Cli {
     /// End PR number for range mode (inclusive)
     #[arg(long)]
     pub to: Option<u64>,
+
+    /// Include the unified diff for PRs
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Only include diff files matching this glob (repeatable, requires --diff)
+    #[arg(long, value_name = "GLOB")]
+    pub diff_include: Vec<String>,
+
+    /// Exclude diff files matching this glob, e.g. '*.lock' (repeatable, requires --diff)
+    #[arg(long, value_name = "GLOB")]
+    pub diff_exclude: Vec<String>,
+
+    /// Include CI check runs and commit statuses for PRs
+    #[arg(long)]
+    pub checks: bool,
+
+    /// Attach log excerpts from failing GitHub Actions jobs (requires --checks)
+    #[arg(long)]
+    pub job_logs: bool,
+
+    /// Maximum size in bytes of each job log excerpt
+    #[arg(long, default_value_t = 4000)]
+    pub job_log_limit: usize,
+
+    /// Also fetch issues and PRs referenced by the item, up to this many hops away
+    #[arg(long, value_name = "DEPTH")]
+    pub follow_refs: Option<u32>,
+
+    /// Maximum number of referenced issues and PRs to fetch in total
+    #[arg(long, default_value_t = 20)]
+    pub follow_refs_limit: usize,
+
+    /// Download embedded images and attachments into the output folder (Markdown only)
+    #[arg(long)]
+    pub attachments: bool,
+
+    /// Maximum size in bytes of each downloaded attachment
+    #[arg(long, default_value_t = 10 * 1024 * 1024)]
+    pub attachment_limit: u64,
+
+    /// Maximum size in bytes of all downloaded attachments together
+    #[arg(long, default_value_t = 50 * 1024 * 1024)]
+    pub attachments_total_limit: u64,
+
+    /// Always fetch from GitHub, without reading or writing the response cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Ignore cached responses, but store the fresh ones
+    #[arg(long)]
+    pub refresh: bool,
+
+    /// Directory for the response cache (default: $XDG_CACHE_HOME/gh-context or ~/.cache/gh-context)
+    #[arg(long, env = "GH_CONTEXT_CACHE_DIR")]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Times to retry a request after a rate limit, network failure or gateway error
+    #[arg(long, default_value_t = 3)]
+    pub retries: u32,
+
+    /// Give up instead of waiting longer than this many seconds for a rate limit to reset
+    #[arg(long, default_value_t = 300)]
+    pub max_retry_wait: u64,
+
+    /// Slow requests down as the rate limit quota runs low (native backend only)
+    #[arg(long)]
+    pub pace: bool,
+
+    /// Number of issues or PRs fetched at the same time in bulk and range modes
+    #[arg(long, default_value_t = 1)]
+    pub jobs: usize,
+
+    /// Save every `gh` invocation and its output to this cassette file (gh backend only)
+    #[arg(long)]
+    pub record: Option<PathBuf>,
+
+    /// Serve `gh` output from a cassette saved by --record instead of running gh
+    #[arg(long)]
+    pub replay: Option<PathBuf>,
+}
+
+// Optional only so that --sync can reject them when given.
+impl Cli {
+    pub fn per_page(&self) -> u32 {
+        self.per_page.unwrap_or(30)
+    }
+
+    pub fn pages(&self) -> u32 {
+        self.pages.unwrap_or(1)
+    }
 }
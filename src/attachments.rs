@@ -0,0 +1,325 @@
+use crate::types::Context;
+use anyhow::{Context as _, Result};
+use serde::Serialize;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+/// Folder inside the output folder that attachments are saved to.
+pub const DIR: &str = "attachments";
+
+/// Size limits for downloaded attachments.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Files larger than this are skipped.
+    pub max_file_bytes: u64,
+    /// Files that would bring the saved total above this are skipped.
+    pub max_total_bytes: u64,
+}
+
+/// One entry of `attachments/manifest.json`.
+#[derive(Debug, Serialize)]
+pub struct ManifestEntry {
+    pub url: String,
+    /// Relative to the output folder; only set for saved files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Why the file was not saved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// Hosts GitHub serves images from, besides uploads: proxied images, raw
+/// repo files, avatars and its own assets.
+const IMAGE_HOSTS: [&str; 4] = [
+    "camo.githubusercontent.com",
+    "raw.githubusercontent.com",
+    "avatars.githubusercontent.com",
+    "github.githubassets.com",
+];
+
+/// Finds attachment URLs in the text of a context: files uploaded to GitHub
+/// (`user-attachments`, repo `assets`/`files`,
+/// `*-user-images.githubusercontent.com`) wherever they appear, and images
+/// on GitHub's image hosts embedded with Markdown or `<img>` tags. Only https
+/// URLs are listed, so images elsewhere, such as tracking pixels or internal
+/// addresses, are never fetched. Each URL is listed once, in order of first
+/// appearance.
+///
+/// Downloads are unauthenticated, so uploads to private repos, which GitHub
+/// only serves to a signed-in browser, end up skipped in the manifest.
+pub fn find_urls(context: &Context) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for text in texts(context) {
+        for url in urls_in(text, &context.metadata.host) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+// Body, comments and replies, reviews and review threads, including those of
+// referenced items.
+fn texts(context: &Context) -> Vec<&str> {
+    let mut texts = vec![context.body.as_str()];
+    for comment in &context.comments {
+        texts.push(&comment.body);
+        texts.extend(comment.replies.iter().map(|r| r.body.as_str()));
+    }
+    for review in context.reviews.iter().flatten() {
+        texts.push(&review.body);
+    }
+    for thread in context.review_threads.iter().flatten() {
+        texts.extend(thread.comments.iter().map(|c| c.body.as_str()));
+    }
+    for reference in &context.references {
+        texts.extend(self::texts(&reference.context));
+    }
+    texts
+}
+
+fn urls_in(text: &str, host: &str) -> Vec<String> {
+    url_spans(text, host)
+        .into_iter()
+        .map(|span| text[span].to_string())
+        .collect()
+}
+
+// Byte ranges of the attachment URLs in `text`, in order and without overlaps.
+fn url_spans(text: &str, host: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+
+    // ![alt](url "title")
+    for (i, _) in text.match_indices("![") {
+        if let Some(close) = text[i..].find("](") {
+            let mut start = i + close + 2;
+            let mut end = start + text[start..].find([')', ' ', '\n']).unwrap_or(text.len() - start);
+            if text[start..end].starts_with('<') && text[start..end].ends_with('>') {
+                start += 1;
+                end -= 1;
+            }
+            spans.push(start..end);
+        }
+    }
+    // <img src="url">
+    for (i, _) in text.match_indices("<img") {
+        let tag_end = text[i..].find('>').map_or(text.len(), |end| i + end);
+        if let Some(src) = text[i..tag_end].find("src=\"") {
+            let start = i + src + 5;
+            spans.push(start..start + text[start..tag_end].find('"').unwrap_or(tag_end - start));
+        }
+    }
+    // Uploaded files, also when only linked
+    for (start, _) in text.match_indices("https://") {
+        let rest = &text[start..];
+        let word = &rest[..rest
+            .find(|c: char| c.is_whitespace() || "()[]<>\"'`".contains(c))
+            .unwrap_or(rest.len())];
+        let word = word.trim_end_matches(['.', ',', ';', ':']);
+        if is_uploaded_file(word, host) {
+            spans.push(start..start + word.len());
+        }
+    }
+
+    spans.retain(|span| {
+        let url = &text[span.clone()];
+        is_uploaded_file(url, host) || is_github_image(url)
+    });
+    spans.sort_by_key(|span| (span.start, std::cmp::Reverse(span.end)));
+    spans.dedup_by(|next, prev| next.start < prev.end);
+    spans
+}
+
+fn is_github_image(url: &str) -> bool {
+    let Some(rest) = url.strip_prefix("https://") else {
+        return false;
+    };
+    let url_host = rest.split('/').next().unwrap_or(rest);
+    IMAGE_HOSTS.contains(&url_host)
+}
+
+fn is_uploaded_file(url: &str, host: &str) -> bool {
+    let Some(rest) = url.strip_prefix("https://") else {
+        return false;
+    };
+    let (url_host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if url_host == "user-images.githubusercontent.com"
+        || url_host == "private-user-images.githubusercontent.com"
+    {
+        return true;
+    }
+    if url_host != host {
+        return false;
+    }
+    // user-attachments/assets/<id>, or <owner>/<repo>/assets|files/<id>/...
+    let parts: Vec<&str> = path.split('/').collect();
+    parts.first() == Some(&"user-attachments")
+        || (parts.len() > 3 && (parts[2] == "assets" || parts[2] == "files"))
+}
+
+/// Downloads `urls` into `<out_dir>/attachments/` and writes
+/// `attachments/manifest.json`. Failed or oversized downloads are recorded in
+/// the manifest rather than failing the run.
+pub fn download_all(urls: &[String], out_dir: &Path, limits: &Limits) -> Result<Vec<ManifestEntry>> {
+    let dir = out_dir.join(DIR);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {:?}", dir))?;
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .user_agent(concat!("gh-context/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .into();
+
+    let mut entries = Vec::new();
+    let mut total = 0_u64;
+    for (i, url) in urls.iter().enumerate() {
+        let mut entry = ManifestEntry {
+            url: url.clone(),
+            path: None,
+            bytes: None,
+            content_type: None,
+            skipped: None,
+        };
+
+        match download(&agent, url, limits.max_file_bytes) {
+            // Files that no longer fit are skipped, but smaller ones after
+            // them may still be saved.
+            Ok((bytes, _)) if total + bytes.len() as u64 > limits.max_total_bytes => {
+                entry.skipped = Some(format!(
+                    "would exceed the total limit of {} bytes",
+                    limits.max_total_bytes
+                ));
+            }
+            Ok((bytes, content_type)) => {
+                let name = format!("{}-{}", i + 1, file_name(url, content_type.as_deref()));
+                let path = dir.join(&name);
+                fs::write(&path, &bytes)
+                    .with_context(|| format!("Failed to write attachment: {:?}", path))?;
+                total += bytes.len() as u64;
+                entry.path = Some(format!("{}/{}", DIR, name));
+                entry.bytes = Some(bytes.len() as u64);
+                entry.content_type = content_type;
+            }
+            Err(Failure::TooLarge) => {
+                entry.skipped = Some(format!("larger than {} bytes", limits.max_file_bytes));
+            }
+            Err(Failure::Error(err)) => entry.skipped = Some(err),
+        }
+        entries.push(entry);
+    }
+
+    let manifest = dir.join("manifest.json");
+    let json = serde_json::to_string_pretty(&entries)
+        .context("Failed to serialize attachment manifest")?;
+    fs::write(&manifest, json)
+        .with_context(|| format!("Failed to write attachment manifest: {:?}", manifest))?;
+
+    Ok(entries)
+}
+
+enum Failure {
+    TooLarge,
+    // Plain text, since it ends up in the manifest
+    Error(String),
+}
+
+fn download(agent: &ureq::Agent, url: &str, limit: u64) -> Result<(Vec<u8>, Option<String>), Failure> {
+    let mut response = agent
+        .get(url)
+        .call()
+        .map_err(|e| Failure::Error(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(Failure::Error(format!(
+            "HTTP status {}",
+            response.status().as_u16()
+        )));
+    }
+
+    let content_type = response
+        .headers()
+        .get("content-type")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(';').next().unwrap_or(v).trim().to_string());
+    let length = response
+        .headers()
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if length.is_some_and(|length| length > limit) {
+        return Err(Failure::TooLarge);
+    }
+
+    let bytes = response
+        .body_mut()
+        .with_config()
+        .limit(limit)
+        .read_to_vec()
+        .map_err(|e| match e {
+            ureq::Error::BodyExceedsLimit(_) => Failure::TooLarge,
+            e => Failure::Error(e.to_string()),
+        })?;
+    Ok((bytes, content_type))
+}
+
+// The last path segment, made safe for a file name, with an extension from
+// the content type when it has none (e.g. `user-attachments/assets/<uuid>`).
+fn file_name(url: &str, content_type: Option<&str>) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let segment = path.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+    let mut name: String = segment
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "._-".contains(c) { c } else { '_' })
+        .take(100)
+        .collect();
+    if name.is_empty() {
+        name = "attachment".to_string();
+    }
+
+    let extension = match content_type {
+        Some("image/png") => "png",
+        Some("image/jpeg") => "jpg",
+        Some("image/gif") => "gif",
+        Some("image/webp") => "webp",
+        Some("image/svg+xml") => "svg",
+        Some("video/mp4") => "mp4",
+        Some("video/quicktime") => "mov",
+        Some("application/pdf") => "pdf",
+        Some("application/zip") => "zip",
+        Some("text/plain") => "txt",
+        _ => "",
+    };
+    if !name.contains('.') && !extension.is_empty() {
+        name = format!("{}.{}", name, extension);
+    }
+    name
+}
+
+/// Points links in rendered Markdown at the saved copies. Only the URLs
+/// [`find_urls`] would find are rewritten, so the same URL in other prose or
+/// inside a longer URL is left alone.
+pub fn rewrite_links(markdown: &str, host: &str, entries: &[ManifestEntry]) -> String {
+    let mut rewritten = String::with_capacity(markdown.len());
+    let mut last = 0;
+    for span in url_spans(markdown, host) {
+        let url = &markdown[span.clone()];
+        let Some(path) = entries
+            .iter()
+            .find(|e| e.url == url)
+            .and_then(|e| e.path.as_deref())
+        else {
+            continue;
+        };
+        rewritten.push_str(&markdown[last..span.start]);
+        rewritten.push_str(path);
+        last = span.end;
+    }
+    rewritten.push_str(&markdown[last..]);
+    rewritten
+}
@@ -0,0 +1,941 @@
+use crate::error::GhError;
+use crate::gh::{Target, TargetType};
+use crate::runner::{CommandRunner, SystemRunner};
+use crate::types::DEFAULT_HOST;
+use crate::wire::{
+    GhAuthor, GhComment, GhCommit, GhCommitAuthor, GhLabel, GhMilestone, GhResponse, GhReview,
+};
+use anyhow::{anyhow, Context as _, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of GitHub data. `fetch_context` and bulk listing only talk to
+/// GitHub through this trait, so the `gh` CLI and direct HTTP access are
+/// interchangeable.
+///
+/// A backend serves a single GitHub host. REST endpoints are given relative
+/// to that host's API root, e.g. `repos/owner/repo/issues/1/timeline`.
+///
+/// Bulk and range runs with `--jobs` share one backend between threads.
+pub trait Backend: Sync {
+    /// The issue or PR with its conversation comments, in the shape of
+    /// `gh issue view --json` / `gh pr view --json` (see [`GhResponse`]).
+    /// Discussions and commits are not supported here.
+    fn view(&self, target: &Target) -> Result<GhResponse>;
+
+    /// Numbers of up to `limit` issues (never PRs) in `owner/repo`.
+    fn list_issue_numbers(&self, repo: &str, state: &str, limit: u64) -> Result<Vec<u64>>;
+
+    /// GETs a REST endpoint, following pagination. Returns one value per page.
+    fn rest_pages(&self, endpoint: &str) -> Result<Vec<Value>>;
+
+    /// GETs a REST endpoint as text with the given `Accept` media type.
+    fn rest_text(&self, endpoint: &str, accept: &str) -> Result<String>;
+
+    /// Runs a single GraphQL request. A response carrying `errors` is an error.
+    fn graphql(&self, query: &str, variables: &[(&str, Value)]) -> Result<Value>;
+
+    /// Runs a GraphQL query, following `$endCursor` pagination the way
+    /// `gh api graphql --paginate` does: the first `pageInfo` in each response
+    /// drives the next request. Returns one response per page.
+    fn graphql_pages(&self, query: &str, variables: &[(&str, Value)]) -> Result<Vec<Value>> {
+        let mut variables = variables.to_vec();
+        let mut pages = Vec::new();
+
+        loop {
+            let page = self.graphql(query, &variables)?;
+            let cursor = find_page_info(&page)
+                .filter(|info| info.get("hasNextPage").and_then(Value::as_bool) == Some(true))
+                .and_then(|info| info.get("endCursor").cloned());
+            pages.push(page);
+
+            match cursor {
+                Some(cursor) => {
+                    variables.retain(|(name, _)| *name != "endCursor");
+                    variables.push(("endCursor", cursor));
+                }
+                None => return Ok(pages),
+            }
+        }
+    }
+
+    /// GETs a single REST resource unless it still has the entity tag
+    /// `etag`. Backends that cannot send conditional requests always fetch it.
+    fn rest_if_changed(&self, endpoint: &str, etag: Option<&str>) -> Result<Conditional> {
+        let _ = etag;
+        Ok(Conditional::Changed {
+            value: self.rest(endpoint)?,
+            etag: None,
+        })
+    }
+
+    /// GETs a single (unpaginated) REST resource.
+    fn rest(&self, endpoint: &str) -> Result<Value> {
+        self.rest_pages(endpoint)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Empty response from {}", endpoint))
+    }
+
+    fn fetch_timeline(&self, target: &Target) -> Result<Vec<Value>> {
+        let endpoint = format!(
+            "repos/{}/{}/issues/{}/timeline?per_page=100",
+            target.owner,
+            target.repo,
+            target.item_number()?
+        );
+        flatten_pages(self.rest_pages(&endpoint)?)
+    }
+}
+
+/// Result of a conditional GET.
+#[derive(Debug)]
+pub enum Conditional {
+    /// The resource still has the entity tag that was sent (HTTP 304).
+    Unchanged,
+    Changed { value: Value, etag: Option<String> },
+}
+
+// Concatenates paginated REST array responses.
+pub fn flatten_pages(pages: Vec<Value>) -> Result<Vec<Value>> {
+    let mut items = Vec::new();
+    for page in pages {
+        match page {
+            Value::Array(page_items) => items.extend(page_items),
+            other => return Err(anyhow!("Expected a JSON array page, got: {}", other)),
+        }
+    }
+    Ok(items)
+}
+
+/// Shells out to the GitHub CLI, reusing its authentication.
+pub struct GhBackend {
+    host: String,
+    runner: Box<dyn CommandRunner>,
+}
+
+impl GhBackend {
+    pub fn new(host: &str) -> Self {
+        GhBackend::with_runner(host, Box::new(SystemRunner))
+    }
+
+    /// Runs `gh` through `runner`, e.g. one that records or replays a
+    /// cassette.
+    pub fn with_runner(host: &str, runner: Box<dyn CommandRunner>) -> Self {
+        GhBackend {
+            host: host.to_string(),
+            runner,
+        }
+    }
+
+    fn is_default_host(&self) -> bool {
+        self.host == DEFAULT_HOST
+    }
+
+    // gh selects the host from a `HOST/OWNER/REPO` repo argument.
+    fn repo_arg(&self, repo: &str) -> String {
+        if self.is_default_host() {
+            repo.to_string()
+        } else {
+            format!("{}/{}", self.host, repo)
+        }
+    }
+
+    // `gh api` takes the host as a flag instead.
+    fn api_args<'a>(&'a self, args: &[&'a str]) -> Vec<&'a str> {
+        let mut full = args.to_vec();
+        if !self.is_default_host() {
+            full.extend(["--hostname", self.host.as_str()]);
+        }
+        full
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueListItem {
+    pub number: u64,
+}
+
+const ISSUE_FIELDS: &str = "title,body,url,author,comments,number,state,stateReason,labels,\
+assignees,milestone,createdAt,updatedAt,closedAt";
+const PR_FIELDS: &str = "title,body,url,author,comments,number,state,isDraft,labels,assignees,\
+milestone,createdAt,updatedAt,closedAt,mergedAt,baseRefName,headRefName,headRefOid,reviews,commits";
+
+impl Backend for GhBackend {
+    fn view(&self, target: &Target) -> Result<GhResponse> {
+        let repo_arg = self.repo_arg(&format!("{}/{}", target.owner, target.repo));
+
+        let (subcommand, fields) = match target.kind {
+            TargetType::Issue => ("issue", ISSUE_FIELDS),
+            TargetType::Pr => ("pr", PR_FIELDS),
+            TargetType::Discussion | TargetType::Commit { .. } => {
+                return Err(unsupported_view_error(&target.kind));
+            }
+        };
+        let num_arg = target.item_number()?.to_string();
+
+        // gh <subcommand> view <number> --repo <owner>/<repo> --comments --json <fields>
+        let stdout = run_gh(self.runner.as_ref(), &[
+            subcommand,
+            "view",
+            &num_arg,
+            "--repo",
+            &repo_arg,
+            "--comments",
+            "--json",
+            fields,
+        ])?;
+
+        serde_json::from_slice(&stdout).context("Failed to parse JSON output from 'gh'")
+    }
+
+    fn list_issue_numbers(&self, repo: &str, state: &str, limit: u64) -> Result<Vec<u64>> {
+        let repo_arg = self.repo_arg(repo);
+        let stdout = run_gh(self.runner.as_ref(), &[
+            "issue",
+            "list",
+            "--repo",
+            &repo_arg,
+            "--state",
+            state,
+            "--limit",
+            &limit.to_string(),
+            "--json",
+            "number",
+        ])?;
+
+        let items: Vec<IssueListItem> = serde_json::from_slice(&stdout)
+            .context("Failed to parse JSON output from 'gh issue list'")?;
+
+        Ok(items.into_iter().map(|item| item.number).collect())
+    }
+
+    fn rest_pages(&self, endpoint: &str) -> Result<Vec<Value>> {
+        let stdout = run_gh(self.runner.as_ref(), &self.api_args(&["api", endpoint, "--method", "GET", "--paginate"]))?;
+        json_pages(&stdout, "gh api")
+    }
+
+    fn rest_text(&self, endpoint: &str, accept: &str) -> Result<String> {
+        let accept_header = format!("Accept: {}", accept);
+        let stdout = run_gh(self.runner.as_ref(), &self.api_args(&[
+            "api",
+            endpoint,
+            "--method",
+            "GET",
+            "-H",
+            &accept_header,
+        ]))?;
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+
+    fn graphql(&self, query: &str, variables: &[(&str, Value)]) -> Result<Value> {
+        let mut args = vec![
+            "api".to_string(),
+            "graphql".to_string(),
+            "-f".to_string(),
+            format!("query={}", query),
+        ];
+        // -f sends a string as-is, -F lets gh convert numbers and booleans.
+        for (name, value) in variables {
+            match value {
+                Value::String(s) => args.extend(["-f".to_string(), format!("{}={}", name, s)]),
+                other => args.extend(["-F".to_string(), format!("{}={}", name, other)]),
+            }
+        }
+
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let stdout = run_gh(self.runner.as_ref(), &self.api_args(&args))?;
+        let response: Value = serde_json::from_slice(&stdout)
+            .context("Failed to parse JSON output from 'gh api graphql'")?;
+        graphql_data(response)
+    }
+}
+
+// With --paginate, gh prints one JSON document per page back to back.
+fn json_pages(stdout: &[u8], label: &str) -> Result<Vec<Value>> {
+    serde_json::Deserializer::from_slice(stdout)
+        .into_iter::<Value>()
+        .collect::<Result<_, _>>()
+        .with_context(|| format!("Failed to parse JSON output from '{}'", label))
+}
+
+// Runs `gh` with the given arguments and returns stdout, turning a non-zero
+// exit into an error carrying gh's stderr.
+fn run_gh(runner: &dyn CommandRunner, args: &[&str]) -> Result<Vec<u8>> {
+    let label = std::iter::once("gh")
+        .chain(args.iter().copied().take(2))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let output = runner.run("gh", args).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            anyhow::Error::new(GhError::GhMissing)
+        } else {
+            anyhow::Error::new(err).context(format!("Failed to execute '{}'", label))
+        }
+    })?;
+
+    if !output.success() {
+        let message = format!("'{}' failed: {}", label, output.stderr);
+        return Err(match gh_failure(&output.stderr, message.clone()) {
+            Some(err) => err.into(),
+            None => anyhow!(message),
+        });
+    }
+
+    Ok(output.stdout.into_bytes())
+}
+
+// gh prints `HTTP <status>: <message>` for API errors, and GraphQL errors
+// and connection problems as plain text.
+pub fn gh_failure(stderr: &str, message: String) -> Option<GhError> {
+    let lower = stderr.to_ascii_lowercase();
+    if let Some(status) = lower
+        .split("http ")
+        .skip(1)
+        .find_map(|rest| rest.get(..3)?.parse::<u16>().ok())
+    {
+        return GhError::from_status(status, message, false, None);
+    }
+    if lower.contains("could not resolve to") {
+        Some(GhError::NotFound(message))
+    } else if lower.contains("gh auth login") || lower.contains("authentication") {
+        Some(GhError::AuthRequired(message))
+    } else if lower.contains("rate limit") {
+        Some(GhError::rate_limited(message))
+    } else if lower.contains("error connecting to") || lower.contains("dial tcp") {
+        Some(GhError::Network(message))
+    } else {
+        None
+    }
+}
+
+/// Talks to the GitHub REST and GraphQL APIs directly over HTTPS, so `gh`
+/// doesn't need to be installed.
+pub struct NativeBackend {
+    agent: ureq::Agent,
+    api_url: String,
+    graphql_url: String,
+    token: String,
+    pace: bool,
+    // Last seen quota per rate limit resource ("core", "graphql", ...).
+    quotas: Mutex<HashMap<String, RateLimit>>,
+}
+
+/// The `x-ratelimit-*` and `Retry-After` headers of a response.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: Option<u64>,
+    /// When the quota resets, in seconds since the Unix epoch.
+    pub reset: Option<u64>,
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimit {
+    fn from_response(response: &ureq::http::Response<ureq::Body>) -> Self {
+        let header = |name: &str| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<u64>().ok())
+        };
+        RateLimit {
+            limit: header("x-ratelimit-limit"),
+            remaining: header("x-ratelimit-remaining"),
+            reset: header("x-ratelimit-reset"),
+            retry_after: header("retry-after"),
+        }
+    }
+
+    pub fn exhausted(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// How long GitHub asked to wait before retrying: `Retry-After` for
+    /// secondary rate limits, or until the reset time once the quota is used
+    /// up. `now` is in seconds since the Unix epoch.
+    pub fn retry_delay(&self, now: u64) -> Option<Duration> {
+        match (self.retry_after, self.reset) {
+            (Some(seconds), _) => Some(Duration::from_secs(seconds)),
+            (None, Some(reset)) if self.exhausted() => {
+                Some(Duration::from_secs(reset.saturating_sub(now)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Pause before the next request with `--pace`. Once less than a fifth of
+    /// the quota is left, the rest is spread evenly until the reset time;
+    /// with none left, it waits for the reset.
+    pub fn pace_delay(&self, now: u64) -> Option<Duration> {
+        let (limit, remaining, reset) = (self.limit?, self.remaining?, self.reset?);
+        let until_reset = reset.saturating_sub(now);
+        if until_reset == 0 || remaining.saturating_mul(5) >= limit {
+            return None;
+        }
+        Some(match remaining {
+            0 => Duration::from_secs(until_reset),
+            _ => Duration::from_secs_f64(until_reset as f64 / remaining as f64),
+        })
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Logs and diffs can be large; this is well above anything worth prompting with.
+const MAX_BODY_BYTES: u64 = 64 * 1024 * 1024;
+
+impl NativeBackend {
+    pub fn new(api_url: &str, token: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .user_agent(concat!("gh-context/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .into();
+
+        let api_url = api_url.trim_end_matches('/').to_string();
+        // GitHub Enterprise Server serves REST under /api/v3 but GraphQL at /api/graphql.
+        let graphql_url = match api_url.strip_suffix("/api/v3") {
+            Some(root) => format!("{}/api/graphql", root),
+            None => format!("{}/graphql", api_url),
+        };
+
+        NativeBackend {
+            agent,
+            api_url,
+            graphql_url,
+            token: token.to_string(),
+            pace: false,
+            quotas: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Slows requests down ahead of time as the rate limit quota runs low,
+    /// rather than running into the limit and waiting for it to reset.
+    pub fn with_pacing(mut self, pace: bool) -> Self {
+        self.pace = pace;
+        self
+    }
+
+    fn wait_for_quota(&self, resource: &str) {
+        if !self.pace {
+            return;
+        }
+        let quota = self.quotas.lock().unwrap().get(resource).copied();
+        if let Some(delay) = quota.and_then(|q| q.pace_delay(unix_now())) {
+            eprintln!(
+                "Pacing requests: {} of {} {} requests left, waiting {:.1}s",
+                quota.and_then(|q| q.remaining).unwrap_or(0),
+                quota.and_then(|q| q.limit).unwrap_or(0),
+                resource,
+                delay.as_secs_f64()
+            );
+            thread::sleep(delay);
+        }
+    }
+
+    // Remembers the quota a response reports for the next `wait_for_quota`.
+    fn record_quota(&self, resource: &str, response: &ureq::http::Response<ureq::Body>) {
+        if !self.pace {
+            return;
+        }
+        let quota = RateLimit::from_response(response);
+        let resource = response
+            .headers()
+            .get("x-ratelimit-resource")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or(resource);
+        if quota.remaining.is_some() {
+            self.quotas.lock().unwrap().insert(resource.to_string(), quota);
+        }
+    }
+
+    /// Connects to `host`, authenticating the way `gh` does: `GH_TOKEN` or
+    /// `GITHUB_TOKEN` for github.com, `GH_ENTERPRISE_TOKEN` or
+    /// `GITHUB_ENTERPRISE_TOKEN` for GitHub Enterprise Server.
+    pub fn from_env(host: &str) -> Result<Self> {
+        let names = if host == DEFAULT_HOST {
+            ["GH_TOKEN", "GITHUB_TOKEN"]
+        } else {
+            ["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"]
+        };
+        let token = names
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|t| !t.is_empty()))
+            .ok_or_else(|| {
+                GhError::AuthRequired(format!(
+                    "The native backend requires {} or {} for {}",
+                    names[0], names[1], host
+                ))
+            })?;
+        Ok(NativeBackend::new(&api_url_for_host(host), &token))
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            endpoint.to_string()
+        } else {
+            format!("{}/{}", self.api_url, endpoint.trim_start_matches('/'))
+        }
+    }
+
+    fn request(&self, url: &str, accept: &str) -> ureq::RequestBuilder<ureq::typestate::WithoutBody> {
+        self.agent
+            .get(url)
+            .header("Authorization", &format!("Bearer {}", self.token))
+            .header("Accept", accept)
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    }
+
+    // Returns the body and the `next` page URL from the Link header.
+    fn get(&self, url: &str, accept: &str) -> Result<(String, Option<String>)> {
+        self.wait_for_quota("core");
+        let mut response = self
+            .request(url, accept)
+            .call()
+            .map_err(|err| network_error(url, err))?;
+        self.record_quota("core", &response);
+
+        let next = response
+            .headers()
+            .get("link")
+            .and_then(|v| v.to_str().ok())
+            .and_then(next_page_url);
+        let body = read_body(&mut response, url)?;
+        Ok((body, next))
+    }
+
+    fn get_json<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<T> {
+        let (body, _) = self.get(&self.url(endpoint), "application/vnd.github+json")?;
+        serde_json::from_str(&body)
+            .with_context(|| format!("Failed to parse JSON response from {}", endpoint))
+    }
+
+    fn get_all<T: serde::de::DeserializeOwned>(&self, endpoint: &str) -> Result<Vec<T>> {
+        let items = flatten_pages(self.rest_pages(endpoint)?)?;
+        items
+            .into_iter()
+            .map(|item| {
+                serde_json::from_value(item)
+                    .with_context(|| format!("Failed to parse JSON response from {}", endpoint))
+            })
+            .collect()
+    }
+}
+
+impl Backend for NativeBackend {
+    fn view(&self, target: &Target) -> Result<GhResponse> {
+        if let TargetType::Discussion | TargetType::Commit { .. } = target.kind {
+            return Err(unsupported_view_error(&target.kind));
+        }
+        let repo = format!("{}/{}", target.owner, target.repo);
+        let number = target.item_number()?;
+
+        let issue: RestIssue = self.get_json(&format!("repos/{}/issues/{}", repo, number))?;
+        let comments: Vec<RestComment> =
+            self.get_all(&format!("repos/{}/issues/{}/comments?per_page=100", repo, number))?;
+
+        let mut response = GhResponse {
+            title: issue.title,
+            body: issue.body.unwrap_or_default(),
+            url: issue.html_url,
+            number: issue.number,
+            comments: comments
+                .into_iter()
+                .map(|c| GhComment {
+                    author: c.user.map(to_author),
+                    body: c.body.unwrap_or_default(),
+                    created_at: c.created_at,
+                    is_answer: false,
+                    replies: Vec::new(),
+                })
+                .collect(),
+            author: issue.user.map(to_author),
+            state: Some(issue.state.to_uppercase()),
+            state_reason: issue.state_reason.map(|r| r.to_uppercase()),
+            is_draft: None,
+            labels: issue
+                .labels
+                .into_iter()
+                .map(|l| GhLabel { name: l.name })
+                .collect(),
+            assignees: issue.assignees.into_iter().map(to_author).collect(),
+            milestone: issue.milestone.map(|m| GhMilestone { title: m.title }),
+            created_at: issue.created_at,
+            updated_at: issue.updated_at,
+            closed_at: issue.closed_at,
+            merged_at: None,
+            base_ref_name: None,
+            head_ref_name: None,
+            head_ref_oid: None,
+            reviews: Vec::new(),
+            commits: Vec::new(),
+            category: None,
+            locked: Some(issue.locked),
+        };
+
+        if let TargetType::Pr = target.kind {
+            let pull: RestPull = self.get_json(&format!("repos/{}/pulls/{}", repo, number))?;
+            let reviews: Vec<RestReview> =
+                self.get_all(&format!("repos/{}/pulls/{}/reviews?per_page=100", repo, number))?;
+            let commits: Vec<RestCommit> =
+                self.get_all(&format!("repos/{}/pulls/{}/commits?per_page=100", repo, number))?;
+
+            // Match gh, which reports PRs without a state reason and merged PRs as MERGED.
+            response.state_reason = None;
+            if pull.merged_at.is_some() {
+                response.state = Some("MERGED".to_string());
+            }
+            response.is_draft = Some(pull.draft);
+            response.merged_at = pull.merged_at;
+            response.base_ref_name = Some(pull.base.r#ref);
+            response.head_ref_name = Some(pull.head.r#ref);
+            response.head_ref_oid = Some(pull.head.sha);
+            response.reviews = reviews
+                .into_iter()
+                .map(|r| GhReview {
+                    author: r.user.map(to_author),
+                    state: r.state,
+                    submitted_at: r.submitted_at,
+                    body: r.body.unwrap_or_default(),
+                })
+                .collect();
+            response.commits = commits.into_iter().map(to_gh_commit).collect();
+        }
+
+        Ok(response)
+    }
+
+    fn list_issue_numbers(&self, repo: &str, state: &str, limit: u64) -> Result<Vec<u64>> {
+        let per_page = limit.clamp(1, 100);
+        let mut numbers = Vec::new();
+        let mut page = 1;
+
+        // The issues endpoint also returns PRs, so keep paging until enough
+        // real issues are collected.
+        while (numbers.len() as u64) < limit {
+            let endpoint = format!(
+                "repos/{}/issues?state={}&per_page={}&page={}",
+                repo, state, per_page, page
+            );
+            let items: Vec<RestIssueListItem> = self.get_json(&endpoint)?;
+            if items.is_empty() {
+                break;
+            }
+            numbers.extend(
+                items
+                    .into_iter()
+                    .filter(|item| item.pull_request.is_none())
+                    .map(|item| item.number),
+            );
+            page += 1;
+        }
+
+        numbers.truncate(limit as usize);
+        Ok(numbers)
+    }
+
+    fn rest_pages(&self, endpoint: &str) -> Result<Vec<Value>> {
+        let mut pages = Vec::new();
+        let mut next = Some(self.url(endpoint));
+
+        while let Some(url) = next {
+            let (body, next_url) = self.get(&url, "application/vnd.github+json")?;
+            pages.push(
+                serde_json::from_str(&body)
+                    .with_context(|| format!("Failed to parse JSON response from {}", url))?,
+            );
+            next = next_url;
+        }
+
+        Ok(pages)
+    }
+
+    fn rest_text(&self, endpoint: &str, accept: &str) -> Result<String> {
+        let (body, _) = self.get(&self.url(endpoint), accept)?;
+        Ok(body)
+    }
+
+    // GitHub does not count 304 responses against the rate limit.
+    fn rest_if_changed(&self, endpoint: &str, etag: Option<&str>) -> Result<Conditional> {
+        let url = self.url(endpoint);
+        let mut request = self.request(&url, "application/vnd.github+json");
+        if let Some(etag) = etag {
+            request = request.header("If-None-Match", etag);
+        }
+        self.wait_for_quota("core");
+        let mut response = request.call().map_err(|err| network_error(&url, err))?;
+        self.record_quota("core", &response);
+        if response.status().as_u16() == 304 {
+            return Ok(Conditional::Unchanged);
+        }
+
+        let etag = response
+            .headers()
+            .get("etag")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = read_body(&mut response, &url)?;
+        let value = serde_json::from_str(&body)
+            .with_context(|| format!("Failed to parse JSON response from {}", url))?;
+        Ok(Conditional::Changed { value, etag })
+    }
+
+    fn graphql(&self, query: &str, variables: &[(&str, Value)]) -> Result<Value> {
+        let url = self.graphql_url.clone();
+        let variables: serde_json::Map<String, Value> = variables
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        let payload = serde_json::json!({ "query": query, "variables": variables });
+
+        self.wait_for_quota("graphql");
+        let mut response = self
+            .agent
+            .post(&url)
+            .header("Authorization", &format!("Bearer {}", self.token))
+            .header("Content-Type", "application/json")
+            .send(payload.to_string())
+            .map_err(|err| network_error(&url, err))?;
+        self.record_quota("graphql", &response);
+
+        let status = response.status();
+        let body = response
+            .body_mut()
+            .read_to_string()
+            .with_context(|| format!("Failed to read response from {}", url))?;
+        if !status.is_success() {
+            let message = format!(
+                "GitHub GraphQL request failed with status {}: {}",
+                status.as_u16(),
+                api_error_message(&body)
+            );
+            return Err(status_error(status.as_u16(), message, &RateLimit::from_response(&response)));
+        }
+
+        let response: Value =
+            serde_json::from_str(&body).context("Failed to parse GraphQL response from GitHub")?;
+        graphql_data(response)
+    }
+}
+
+// Reads a REST response body, turning an error status into an error.
+fn read_body(response: &mut ureq::http::Response<ureq::Body>, url: &str) -> Result<String> {
+    let status = response.status();
+    let body = response
+        .body_mut()
+        .with_config()
+        .limit(MAX_BODY_BYTES)
+        .read_to_string()
+        .with_context(|| format!("Failed to read response from {}", url))?;
+
+    if !status.is_success() {
+        let message = format!(
+            "GitHub API request to {} failed with status {}: {}",
+            url,
+            status.as_u16(),
+            api_error_message(&body)
+        );
+        return Err(status_error(status.as_u16(), message, &RateLimit::from_response(response)));
+    }
+    Ok(body)
+}
+
+fn status_error(status: u16, message: String, rate_limit: &RateLimit) -> anyhow::Error {
+    let retry_after = rate_limit.retry_delay(unix_now());
+    match GhError::from_status(status, message.clone(), rate_limit.exhausted(), retry_after) {
+        Some(err) => err.into(),
+        None => anyhow!(message),
+    }
+}
+
+fn network_error(url: &str, err: ureq::Error) -> anyhow::Error {
+    GhError::Network(format!("Request to {} failed: {}", url, err)).into()
+}
+
+// GraphQL reports missing and inaccessible nodes in `errors[].type` with a
+// 200 status.
+fn graphql_data(response: Value) -> Result<Value> {
+    if let Some(errors) = response.get("errors").filter(|e| !e.is_null()) {
+        let message = format!("GitHub GraphQL query failed: {}", errors);
+        let error_type = errors.pointer("/0/type").and_then(Value::as_str);
+        return Err(match error_type {
+            Some("NOT_FOUND") => GhError::NotFound(message).into(),
+            Some("FORBIDDEN") => GhError::Forbidden(message).into(),
+            Some("RATE_LIMITED") => GhError::rate_limited(message).into(),
+            _ => anyhow!(message),
+        });
+    }
+    Ok(response)
+}
+
+/// REST API root for a host: api.github.com for github.com, `/api/v3` on
+/// GitHub Enterprise Server.
+pub fn api_url_for_host(host: &str) -> String {
+    if host == DEFAULT_HOST {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{}/api/v3", host)
+    }
+}
+
+// Extracts the `rel="next"` URL from a Link header.
+pub fn next_page_url(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+fn find_page_info(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Object(map) => map
+            .get("pageInfo")
+            .or_else(|| map.values().find_map(find_page_info)),
+        Value::Array(items) => items.iter().find_map(find_page_info),
+        _ => None,
+    }
+}
+
+// `fetch_context` reads discussions through GraphQL and commits through
+// their REST endpoints, never through `view`.
+fn unsupported_view_error(kind: &TargetType) -> anyhow::Error {
+    anyhow!("{:?} targets cannot be fetched with Backend::view", kind)
+}
+
+fn api_error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v.get("message").and_then(Value::as_str).map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+fn to_author(user: RestUser) -> GhAuthor {
+    GhAuthor { login: user.login }
+}
+
+fn to_gh_commit(raw: RestCommit) -> GhCommit {
+    let (headline, body) = match raw.commit.message.split_once('\n') {
+        Some((headline, body)) => (headline.to_string(), body.trim().to_string()),
+        None => (raw.commit.message, String::new()),
+    };
+    let author = GhCommitAuthor {
+        login: raw.author.map(|a| a.login).unwrap_or_default(),
+        name: raw.commit.author.as_ref().map(|a| a.name.clone()).unwrap_or_default(),
+    };
+
+    GhCommit {
+        oid: raw.sha,
+        authors: vec![author],
+        authored_date: raw.commit.author.and_then(|a| a.date),
+        message_headline: headline,
+        message_body: body,
+    }
+}
+
+// REST API shapes used by the native backend
+
+#[derive(Debug, Deserialize)]
+struct RestUser {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestLabel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestMilestone {
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestIssue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    html_url: String,
+    user: Option<RestUser>,
+    state: String,
+    state_reason: Option<String>,
+    #[serde(default)]
+    labels: Vec<RestLabel>,
+    #[serde(default)]
+    assignees: Vec<RestUser>,
+    milestone: Option<RestMilestone>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    closed_at: Option<String>,
+    #[serde(default)]
+    locked: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestIssueListItem {
+    number: u64,
+    pull_request: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestComment {
+    user: Option<RestUser>,
+    body: Option<String>,
+    created_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestBranch {
+    r#ref: String,
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestPull {
+    #[serde(default)]
+    draft: bool,
+    merged_at: Option<String>,
+    base: RestBranch,
+    head: RestBranch,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestReview {
+    user: Option<RestUser>,
+    state: String,
+    submitted_at: Option<String>,
+    body: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestCommit {
+    sha: String,
+    author: Option<RestUser>,
+    commit: RestCommitDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestCommitDetail {
+    message: String,
+    author: Option<RestGitActor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RestGitActor {
+    name: String,
+    date: Option<String>,
+}
@@ -0,0 +1,105 @@
+use crate::format::{render, Format};
+use crate::gh::{Target, TargetType};
+use crate::jobs;
+use crate::report::{self, Failure, Outcome};
+use crate::types::Context;
+use anyhow::{Context as _, Result};
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+/// A bulk, range or sync run: items of one repo and kind, fetched with up to
+/// `jobs` threads and written into `out_dir` as
+/// `<repo>-<type>-<number>.<extension>`.
+pub struct Batch<'a> {
+    pub host: &'a str,
+    pub owner: &'a str,
+    pub repo: &'a str,
+    pub kind: TargetType,
+    pub out_dir: &'a Path,
+    pub format: Format,
+    pub jobs: usize,
+}
+
+impl Batch<'_> {
+    /// Fetches and writes the item numbered `number(item)` for each of
+    /// `items`, in order. `record` is told about each item once its file is
+    /// written (with the file name) or it failed. Failures do not stop the
+    /// run and are returned; errors from `record` or writing a file do.
+    pub fn run<I: Sync>(
+        &self,
+        items: &[I],
+        number: impl Fn(&I) -> u64 + Sync,
+        fetch: impl Fn(&Target) -> Result<Context> + Sync,
+        mut record: impl FnMut(&I, Result<&str, &Failure>) -> Result<()>,
+    ) -> Result<Vec<Failure>> {
+        let mut failures = Vec::new();
+        let work = |item: &I| {
+            let target = Target {
+                host: self.host.to_string(),
+                owner: self.owner.to_string(),
+                repo: self.repo.to_string(),
+                number: Some(number(item)),
+                kind: self.kind.clone(),
+            };
+            fetch(&target)
+        };
+        jobs::run_ordered(items, self.jobs, work, |item, result| {
+            let context = match result {
+                Ok(context) => context,
+                Err(err) => {
+                    let failure = Failure::new(number(item), &err);
+                    record(item, Err(&failure))?;
+                    failures.push(failure);
+                    return Ok(());
+                }
+            };
+            let file_name = format!(
+                "{}-{}-{}.{}",
+                self.repo,
+                context.metadata.r#type,
+                number(item),
+                self.format.extension()
+            );
+            let file_path = self.out_dir.join(&file_name);
+            fs::write(&file_path, render(&context, self.format)?)
+                .with_context(|| format!("Failed to write output to file: {:?}", file_path))?;
+            record(item, Ok(&file_name))?;
+            println!("Generated context in {}", file_path.display());
+            Ok(())
+        })?;
+        Ok(failures)
+    }
+
+    /// Reports `failures` on stderr and in the failure report, and maps the
+    /// outcome to the exit code. `item_label` names the items ("issue",
+    /// "PR") and `scope` the run ("for owner/repo").
+    pub fn finish(
+        &self,
+        item_label: &str,
+        scope: &str,
+        succeeded: usize,
+        failures: &[Failure],
+    ) -> Result<ExitCode> {
+        if !failures.is_empty() {
+            eprintln!(
+                "Completed with {} failed {}(s) {}",
+                failures.len(),
+                item_label,
+                scope
+            );
+            for failure in failures {
+                eprintln!(" - {} #{}: {}", item_label, failure.number, failure.message);
+            }
+        }
+        if let Some(path) = report::write(self.out_dir, failures)? {
+            eprintln!("Failure report written to {}", path.display());
+        }
+
+        let outcome = Outcome::of(succeeded, failures.len());
+        if outcome == Outcome::Failed {
+            eprintln!("Error: No {} context files were generated {}", item_label, scope);
+        }
+        Ok(outcome.exit_code(failures))
+    }
+}
@@ -1,20 +1,29 @@
 use crate::args::Cli;
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
+use gh_context::error::invalid_args;
+use gh_context::gh::MAX_BULK_ITEMS;
 use std::fs;
 use std::path::PathBuf;
 
 pub fn validate_bulk_args(cli: &Cli) -> Result<()> {
     if cli.pr {
-        return Err(anyhow!("--bulk supports issues only; remove --pr"));
+        return Err(invalid_args("--bulk supports issues only; remove --pr"));
     }
     if cli.clip {
-        return Err(anyhow!("--clip is not supported with --bulk"));
+        return Err(invalid_args("--clip is not supported with --bulk"));
     }
-    if cli.per_page == 0 || cli.per_page > 100 {
-        return Err(anyhow!("--per-page must be between 1 and 100"));
+    if cli.per_page() == 0 || cli.per_page() > 100 {
+        return Err(invalid_args("--per-page must be between 1 and 100"));
     }
-    if cli.pages == 0 {
-        return Err(anyhow!("--pages must be at least 1"));
+    if cli.pages() == 0 {
+        return Err(invalid_args("--pages must be at least 1"));
+    }
+    let limit = u64::from(cli.per_page()) * u64::from(cli.pages());
+    if limit > MAX_BULK_ITEMS {
+        return Err(invalid_args(format!(
+            "Requested {} items (--per-page * --pages) exceeds maximum allowed of {}",
+            limit, MAX_BULK_ITEMS
+        )));
     }
     Ok(())
 }
@@ -23,27 +32,63 @@ pub fn resolve_bulk_out_dir(cli: &Cli, repo: &str) -> Result<PathBuf> {
     resolve_out_dir(cli, format!("{}-issues", repo), "bulk mode")
 }
 
+pub fn validate_sync_args(cli: &Cli) -> Result<()> {
+    if cli.sync && !cli.bulk {
+        return Err(invalid_args("--sync requires --bulk"));
+    }
+    if cli.sync_full && !cli.sync {
+        return Err(invalid_args("--sync-full requires --sync"));
+    }
+    if cli.sync && (cli.per_page.is_some() || cli.pages.is_some()) {
+        return Err(invalid_args("--per-page and --pages cannot be used with --sync, which lists every issue"));
+    }
+    Ok(())
+}
+
+pub fn validate_resume_args(cli: &Cli) -> Result<()> {
+    if !cli.resume {
+        return Ok(());
+    }
+    if !cli.bulk && cli.from.is_none() && cli.to.is_none() {
+        return Err(invalid_args("--resume requires --bulk or --from/--to"));
+    }
+    if cli.sync {
+        return Err(invalid_args("--resume cannot be used with --sync, which skips up-to-date issues on its own"));
+    }
+    Ok(())
+}
+
+pub fn validate_jobs_args(cli: &Cli) -> Result<()> {
+    if cli.jobs == 0 {
+        return Err(invalid_args("--jobs must be at least 1"));
+    }
+    if cli.jobs > 1 && !cli.bulk && cli.from.is_none() && cli.to.is_none() {
+        return Err(invalid_args("--jobs requires --bulk or --from/--to"));
+    }
+    Ok(())
+}
+
 pub fn validate_pr_range_args(cli: &Cli) -> Result<(u64, u64)> {
     if cli.bulk {
-        return Err(anyhow!("--from/--to cannot be used with --bulk"));
+        return Err(invalid_args("--from/--to cannot be used with --bulk"));
     }
     if cli.issue {
-        return Err(anyhow!("--from/--to supports PRs only; remove --issue"));
+        return Err(invalid_args("--from/--to supports PRs only; remove --issue"));
     }
     if cli.clip {
-        return Err(anyhow!("--clip is not supported with --from/--to"));
+        return Err(invalid_args("--clip is not supported with --from/--to"));
     }
 
     let (from, to) = match (cli.from, cli.to) {
         (Some(from), Some(to)) => (from, to),
         (Some(_), None) | (None, Some(_)) => {
-            return Err(anyhow!("--from and --to must be provided together"));
+            return Err(invalid_args("--from and --to must be provided together"));
         }
-        (None, None) => return Err(anyhow!("--from and --to are required for PR range mode")),
+        (None, None) => return Err(invalid_args("--from and --to are required for PR range mode")),
     };
 
     if from > to {
-        return Err(anyhow!("--from must be less than or equal to --to"));
+        return Err(invalid_args("--from must be less than or equal to --to"));
     }
 
     Ok((from, to))
@@ -56,7 +101,7 @@ pub fn resolve_pr_range_out_dir(cli: &Cli, repo: &str) -> Result<PathBuf> {
 fn resolve_out_dir(cli: &Cli, default_name: String, mode_label: &str) -> Result<PathBuf> {
     let dir = if let Some(path) = &cli.out {
         if path.exists() && path.is_file() {
-            return Err(anyhow!("--out must be a directory in {}", mode_label));
+            return Err(invalid_args(format!("--out must be a directory in {}", mode_label)));
         }
         path.clone()
     } else {
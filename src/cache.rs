@@ -0,0 +1,258 @@
+use crate::backend::{Backend, Conditional};
+use crate::gh::{self, FetchOptions, Target, TargetType};
+use crate::graphql;
+use crate::refs;
+use crate::types::Context;
+use crate::wire::GhResponse;
+use anyhow::{Context as _, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// On-disk cache of the raw GitHub responses behind each item's context.
+///
+/// Entries are keyed by host, repo, type and number, and remember the item's
+/// `updated_at` and ETag. Before an entry is used, one cheap request checks
+/// that the item has not changed since; a changed item is fetched again and
+/// its entry replaced. Items without an entry are fetched right away, with no
+/// check. Commits have no update time and are not cached.
+pub struct Cache {
+    dir: PathBuf,
+    refresh: bool,
+}
+
+/// What the item looked like when its responses were stored.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct Stamp {
+    updated_at: Option<String>,
+    etag: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    stamp: Stamp,
+    responses: BTreeMap<String, Value>,
+}
+
+impl Cache {
+    /// With `refresh`, stored responses are never used but entries are
+    /// still rewritten.
+    pub fn new(dir: PathBuf, refresh: bool) -> Self {
+        Cache { dir, refresh }
+    }
+
+    /// `$XDG_CACHE_HOME/gh-context`, or `~/.cache/gh-context`.
+    pub fn default_dir() -> Option<PathBuf> {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(base.join("gh-context"))
+    }
+
+    /// `gh::fetch_context` through the cache.
+    ///
+    /// Responses that were not stored yet (e.g. the diff, when `--diff` is
+    /// new) are fetched and added. CI checks and job logs change without
+    /// touching the item, so they are always fetched. Referenced items get
+    /// entries and checks of their own.
+    pub fn fetch_context(
+        &self,
+        backend: &dyn Backend,
+        target: &Target,
+        options: &FetchOptions,
+    ) -> Result<Context> {
+        let Some(path) = self.path(target) else {
+            return gh::fetch_context(backend, target, options);
+        };
+
+        let cached = if self.refresh { None } else { load(&path) };
+        let (stamp, replay) = match cached {
+            Some(entry) => {
+                let (stamp, fresh) = check(backend, target, &entry.stamp)?;
+                let replay = if fresh { entry.responses } else { BTreeMap::new() };
+                (Some(stamp), replay)
+            }
+            None => (None, BTreeMap::new()),
+        };
+
+        let recorder = Recorder {
+            inner: backend,
+            replay,
+            recorded: Mutex::new(BTreeMap::new()),
+        };
+        let item_options = FetchOptions {
+            follow_refs: None,
+            ..options.clone()
+        };
+        let mut context = gh::fetch_context(&recorder, target, &item_options)?;
+
+        // Without a check, the update time comes from the fetched item. The
+        // ETag is picked up by the next check.
+        let stamp = stamp.unwrap_or_else(|| Stamp {
+            updated_at: context.metadata.updated_at.clone(),
+            etag: None,
+        });
+        let entry = Entry {
+            stamp,
+            responses: recorder.recorded.into_inner().unwrap_or_default(),
+        };
+        if let Err(err) = save(&path, &entry) {
+            eprintln!("Warning: failed to update cache: {:#}", err);
+        }
+
+        if let Some(follow) = &options.follow_refs {
+            let fetch = |target: &Target, options: &FetchOptions| self.fetch_context(backend, target, options);
+            refs::expand(backend, target, &mut context, follow, options, &fetch)?;
+        }
+        Ok(context)
+    }
+
+    fn path(&self, target: &Target) -> Option<PathBuf> {
+        let kind = match target.kind {
+            TargetType::Issue => "issue",
+            TargetType::Pr => "pr",
+            TargetType::Discussion => "discussion",
+            TargetType::Commit { .. } => return None,
+        };
+        Some(
+            self.dir
+                .join(target.host.to_ascii_lowercase())
+                .join(target.owner.to_ascii_lowercase())
+                .join(target.repo.to_ascii_lowercase())
+                .join(format!("{}-{}.json", kind, target.number?)),
+        )
+    }
+}
+
+// An unreadable entry (missing, or written by another version) is a miss.
+fn load(path: &Path) -> Option<Entry> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn save(path: &Path, entry: &Entry) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory: {:?}", dir))?;
+    }
+    let json = serde_json::to_string(entry).context("Failed to serialize cache entry")?;
+    // Written whole and renamed, so an interrupted run never leaves half an entry.
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).with_context(|| format!("Failed to write cache entry: {:?}", tmp))?;
+    fs::rename(&tmp, path).with_context(|| format!("Failed to write cache entry: {:?}", path))
+}
+
+// Returns the item's current stamp and whether `cached` still matches it.
+// Issues and PRs send a conditional request for the issue resource, which
+// also covers PRs; discussions only have GraphQL, so compare `updatedAt`.
+fn check(backend: &dyn Backend, target: &Target, cached: &Stamp) -> Result<(Stamp, bool)> {
+    let same_time =
+        |updated_at: &Option<String>| updated_at.is_some() && &cached.updated_at == updated_at;
+
+    if target.kind == TargetType::Discussion {
+        let updated_at = graphql::updated_at(backend, target)?;
+        let fresh = same_time(&updated_at);
+        return Ok((Stamp { updated_at, etag: None }, fresh));
+    }
+
+    let endpoint = format!(
+        "repos/{}/{}/issues/{}",
+        target.owner,
+        target.repo,
+        target.item_number()?
+    );
+    match backend.rest_if_changed(&endpoint, cached.etag.as_deref())? {
+        Conditional::Unchanged => Ok((cached.clone(), true)),
+        Conditional::Changed { value, etag } => {
+            let updated_at = value
+                .get("updated_at")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            let fresh = same_time(&updated_at);
+            Ok((Stamp { updated_at, etag }, fresh))
+        }
+    }
+}
+
+// Serves stored responses where it has them and records every response the
+// fetch used, so an entry only keeps what the current options need.
+struct Recorder<'a> {
+    inner: &'a dyn Backend,
+    replay: BTreeMap<String, Value>,
+    recorded: Mutex<BTreeMap<String, Value>>,
+}
+
+impl Recorder<'_> {
+    fn call(&self, key: String, fetch: impl FnOnce() -> Result<Value>) -> Result<Value> {
+        let value = match self.replay.get(&key) {
+            Some(value) => value.clone(),
+            None => fetch()?,
+        };
+        if let Ok(mut recorded) = self.recorded.lock() {
+            recorded.insert(key, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+// Check runs, commit statuses, annotations and job logs.
+fn is_volatile(endpoint: &str) -> bool {
+    endpoint.contains("/check-runs") || endpoint.contains("/actions/") || endpoint.ends_with("/status")
+}
+
+impl Backend for Recorder<'_> {
+    fn view(&self, target: &Target) -> Result<GhResponse> {
+        let key = format!(
+            "view {:?} {}/{}#{}",
+            target.kind,
+            target.owner,
+            target.repo,
+            target.item_number()?
+        );
+        let value = self.call(key, || Ok(serde_json::to_value(self.inner.view(target)?)?))?;
+        serde_json::from_value(value).context("Failed to parse cached issue/PR")
+    }
+
+    fn list_issue_numbers(&self, repo: &str, state: &str, limit: u64) -> Result<Vec<u64>> {
+        self.inner.list_issue_numbers(repo, state, limit)
+    }
+
+    fn rest_pages(&self, endpoint: &str) -> Result<Vec<Value>> {
+        if is_volatile(endpoint) {
+            return self.inner.rest_pages(endpoint);
+        }
+        let value = self.call(format!("rest {}", endpoint), || {
+            Ok(Value::Array(self.inner.rest_pages(endpoint)?))
+        })?;
+        match value {
+            Value::Array(pages) => Ok(pages),
+            other => Ok(vec![other]),
+        }
+    }
+
+    fn rest_text(&self, endpoint: &str, accept: &str) -> Result<String> {
+        if is_volatile(endpoint) {
+            return self.inner.rest_text(endpoint, accept);
+        }
+        let value = self.call(format!("text {} {}", accept, endpoint), || {
+            Ok(Value::String(self.inner.rest_text(endpoint, accept)?))
+        })?;
+        Ok(value.as_str().unwrap_or_default().to_string())
+    }
+
+    fn graphql(&self, query: &str, variables: &[(&str, Value)]) -> Result<Value> {
+        let variables_key: BTreeMap<&str, &Value> =
+            variables.iter().map(|(name, value)| (*name, value)).collect();
+        let key = format!("graphql {} {}", query, serde_json::json!(variables_key));
+        self.call(key, || self.inner.graphql(query, variables))
+    }
+
+    fn rest_if_changed(&self, endpoint: &str, etag: Option<&str>) -> Result<Conditional> {
+        self.inner.rest_if_changed(endpoint, etag)
+    }
+}
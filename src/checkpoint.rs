@@ -0,0 +1,151 @@
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Written into the output directory of bulk and range runs.
+pub const MANIFEST_FILE: &str = ".gh-context-checkpoint.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pending,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemProgress {
+    pub status: Status,
+    /// File name inside the output directory, once written.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    /// Describes the run, e.g. `range owner/repo 1..=500`, so `--resume`
+    /// never continues a different one.
+    run: String,
+    items: BTreeMap<u64, ItemProgress>,
+}
+
+/// Per-item progress of a bulk or range run, saved after every item so an
+/// interrupted run can be resumed.
+#[derive(Debug)]
+pub struct Checkpoint {
+    dir: PathBuf,
+    manifest: Manifest,
+}
+
+impl Checkpoint {
+    /// Starts a manifest for `run` with every item pending, replacing any
+    /// earlier one in `dir`.
+    pub fn start(dir: &Path, run: &str, items: &[u64]) -> Result<Self> {
+        let pending = ItemProgress {
+            status: Status::Pending,
+            file: None,
+            error: None,
+        };
+        let checkpoint = Checkpoint {
+            dir: dir.to_path_buf(),
+            manifest: Manifest {
+                run: run.to_string(),
+                items: items.iter().map(|&n| (n, pending.clone())).collect(),
+            },
+        };
+        checkpoint.save()?;
+        Ok(checkpoint)
+    }
+
+    /// Reopens the manifest that an earlier `run` left in `dir`.
+    pub fn resume(dir: &Path, run: &str) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        let json = fs::read_to_string(&path).with_context(|| {
+            format!("Nothing to resume: failed to read checkpoint manifest {:?}", path)
+        })?;
+        let manifest: Manifest = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse checkpoint manifest: {:?}", path))?;
+        if manifest.run != run {
+            return Err(anyhow!(
+                "{:?} records a different run ({}); rerun without --resume to start over",
+                path,
+                manifest.run
+            ));
+        }
+        Ok(Checkpoint {
+            dir: dir.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Items still to do: pending and failed ones, and done ones whose file
+    /// has gone missing.
+    pub fn remaining(&self) -> Vec<u64> {
+        self.manifest
+            .items
+            .iter()
+            .filter(|(_, item)| match (&item.status, &item.file) {
+                (Status::Done, Some(file)) => !self.dir.join(file).exists(),
+                _ => true,
+            })
+            .map(|(&number, _)| number)
+            .collect()
+    }
+
+    pub fn total(&self) -> usize {
+        self.manifest.items.len()
+    }
+
+    /// Items done so far, in this run or the ones it resumes.
+    pub fn done_count(&self) -> usize {
+        self.manifest
+            .items
+            .values()
+            .filter(|item| item.status == Status::Done)
+            .count()
+    }
+
+    pub fn done(&mut self, number: u64, file: &str) -> Result<()> {
+        self.set(
+            number,
+            ItemProgress {
+                status: Status::Done,
+                file: Some(file.to_string()),
+                error: None,
+            },
+        )
+    }
+
+    pub fn failed(&mut self, number: u64, error: &str) -> Result<()> {
+        self.set(
+            number,
+            ItemProgress {
+                status: Status::Failed,
+                file: None,
+                error: Some(error.to_string()),
+            },
+        )
+    }
+
+    fn set(&mut self, number: u64, progress: ItemProgress) -> Result<()> {
+        self.manifest.items.insert(number, progress);
+        self.save()
+    }
+
+    // Written whole and renamed, so a run killed mid-write keeps the last
+    // complete manifest.
+    fn save(&self) -> Result<()> {
+        let path = self.dir.join(MANIFEST_FILE);
+        let tmp = self.dir.join(format!("{}.tmp", MANIFEST_FILE));
+        let json = serde_json::to_string_pretty(&self.manifest)
+            .context("Failed to serialize checkpoint manifest")?;
+        fs::write(&tmp, json)
+            .with_context(|| format!("Failed to write checkpoint manifest: {:?}", tmp))?;
+        fs::rename(&tmp, &path)
+            .with_context(|| format!("Failed to write checkpoint manifest: {:?}", path))
+    }
+}
@@ -0,0 +1,60 @@
+use crate::error::invalid_args;
+use crate::types::FileDiff;
+use anyhow::Result;
+use glob::Pattern;
+
+#[derive(Debug, Default, Clone)]
+pub struct DiffFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl DiffFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(DiffFilter {
+            include: compile_patterns(include)?,
+            exclude: compile_patterns(exclude)?,
+        })
+    }
+
+    // A file is kept when it matches any include pattern (or none were given)
+    // and matches no exclude pattern.
+    pub fn matches(&self, path: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| p.matches(path));
+        included && !self.exclude.iter().any(|p| p.matches(path))
+    }
+}
+
+fn compile_patterns(globs: &[String]) -> Result<Vec<Pattern>> {
+    globs
+        .iter()
+        .map(|g| Pattern::new(g).map_err(|err| invalid_args(format!("Invalid diff glob {}: {}", g, err))))
+        .collect()
+}
+
+/// Splits a unified `git diff` into one patch per file.
+pub fn split_patch(patch: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+
+    for line in patch.split_inclusive('\n') {
+        if let Some(header) = line.strip_prefix("diff --git ") {
+            files.push(FileDiff {
+                path: path_from_header(header.trim_end()),
+                patch: String::new(),
+            });
+        }
+        if let Some(file) = files.last_mut() {
+            file.patch.push_str(line);
+        }
+    }
+
+    files
+}
+
+// Header looks like `a/<old> b/<new>`; the new path is what reviewers see.
+fn path_from_header(header: &str) -> String {
+    match header.rfind(" b/") {
+        Some(idx) => header[idx + 3..].to_string(),
+        None => header.to_string(),
+    }
+}
@@ -0,0 +1,166 @@
+use serde::Serialize;
+use std::fmt;
+use std::time::Duration;
+
+/// A failure talking to GitHub, raised where its cause is known (an HTTP
+/// status, GraphQL error type or `gh` output) and carried through `anyhow`
+/// so callers can tell causes apart with [`ErrorKind::of`].
+#[derive(Debug)]
+pub enum GhError {
+    /// The repo, issue, PR, discussion or commit does not exist, or is
+    /// private and invisible to the token.
+    NotFound(String),
+    /// No credentials, or credentials GitHub rejected.
+    AuthRequired(String),
+    /// Authenticated, but not allowed to read the resource.
+    Forbidden(String),
+    /// Out of API quota, or throttled by a secondary rate limit.
+    /// `retry_after` is how long GitHub asked to wait, when it said.
+    RateLimited {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// GitHub could not be reached, or answered with a gateway error.
+    Network(String),
+    /// The `gh` backend is selected but `gh` is not installed.
+    GhMissing,
+}
+
+impl fmt::Display for GhError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GhError::NotFound(message)
+            | GhError::AuthRequired(message)
+            | GhError::Forbidden(message)
+            | GhError::RateLimited { message, .. }
+            | GhError::Network(message) => f.write_str(message),
+            GhError::GhMissing => f.write_str(
+                "Failed to execute 'gh'. Is gh installed? Install it from https://cli.github.com or use --backend native",
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GhError {}
+
+/// Arguments or options that cannot be used, alone or together. Raised
+/// before anything is fetched.
+#[derive(Debug)]
+pub struct InvalidArgs(pub String);
+
+impl fmt::Display for InvalidArgs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidArgs {}
+
+/// An [`InvalidArgs`] error with `message`.
+pub fn invalid_args(message: impl Into<String>) -> anyhow::Error {
+    InvalidArgs(message.into()).into()
+}
+
+impl GhError {
+    /// The first `GhError` in the error's context chain.
+    pub fn find(err: &anyhow::Error) -> Option<&GhError> {
+        err.chain().find_map(|cause| cause.downcast_ref::<GhError>())
+    }
+
+    pub fn rate_limited(message: String) -> Self {
+        GhError::RateLimited {
+            message,
+            retry_after: None,
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            GhError::NotFound(_) => ErrorKind::NotFound,
+            GhError::AuthRequired(_) => ErrorKind::AuthRequired,
+            GhError::Forbidden(_) => ErrorKind::Forbidden,
+            GhError::RateLimited { .. } => ErrorKind::RateLimited,
+            GhError::Network(_) => ErrorKind::Network,
+            GhError::GhMissing => ErrorKind::GhMissing,
+        }
+    }
+
+    /// Maps an unsuccessful HTTP status to its error, if it has a specific
+    /// one. A 403 is a rate limit when GitHub says so in the message or the
+    /// remaining quota is zero. Gateway errors count as network failures,
+    /// since they pass once GitHub recovers.
+    pub fn from_status(
+        status: u16,
+        message: String,
+        quota_exhausted: bool,
+        retry_after: Option<Duration>,
+    ) -> Option<Self> {
+        let rate_limited = |message| GhError::RateLimited {
+            message,
+            retry_after,
+        };
+        match status {
+            404 | 410 => Some(GhError::NotFound(message)),
+            401 => Some(GhError::AuthRequired(message)),
+            429 => Some(rate_limited(message)),
+            403 if quota_exhausted || message.to_ascii_lowercase().contains("rate limit") => {
+                Some(rate_limited(message))
+            }
+            403 => Some(GhError::Forbidden(message)),
+            502..=504 => Some(GhError::Network(message)),
+            _ => None,
+        }
+    }
+}
+
+/// Cause of an error, as reported in `failures.json` and the exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    AuthRequired,
+    Forbidden,
+    RateLimited,
+    Network,
+    GhMissing,
+    /// A response did not have the expected shape.
+    Parse,
+    InvalidArgs,
+    Other,
+}
+
+impl ErrorKind {
+    /// Finds the cause anywhere in the error's context chain. JSON errors
+    /// mean a response did not match the expected schema.
+    pub fn of(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| {
+                if let Some(gh_error) = cause.downcast_ref::<GhError>() {
+                    Some(gh_error.kind())
+                } else if cause.is::<InvalidArgs>() {
+                    Some(ErrorKind::InvalidArgs)
+                } else if cause.is::<serde_json::Error>() {
+                    Some(ErrorKind::Parse)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(ErrorKind::Other)
+    }
+
+    /// Process exit code for a run that stopped on this error. 3 is taken by
+    /// partially failed bulk and range runs.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::InvalidArgs => 2,
+            ErrorKind::NotFound => 4,
+            ErrorKind::AuthRequired => 5,
+            ErrorKind::Forbidden => 6,
+            ErrorKind::RateLimited => 7,
+            ErrorKind::Network => 8,
+            ErrorKind::GhMissing => 9,
+            ErrorKind::Parse => 10,
+        }
+    }
+}
@@ -1,49 +1,589 @@
-use crate::types::Context;
+use crate::types::{
+    is_failing_conclusion, CheckRun, Checks, Context, EventKind, Metadata, Review, ReviewThread,
+    TimelineEvent, UnifiedComment, DEFAULT_HOST,
+};
 use anyhow::{Context as _, Result};
+use std::collections::BTreeMap;
+
+/// How a context is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    /// Comments and timeline events in separate sections.
+    Markdown,
+    /// Comments and timeline events merged into one stream, oldest first.
+    MarkdownChronological,
+}
+
+impl Format {
+    /// File extension for output in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Markdown | Format::MarkdownChronological => "md",
+        }
+    }
+}
+
+pub fn render(context: &Context, format: Format) -> Result<String> {
+    match format {
+        Format::Json => to_json(context),
+        Format::Markdown => Ok(to_markdown(context)),
+        Format::MarkdownChronological => Ok(to_markdown_chronological(context)),
+    }
+}
 
 pub fn to_json(context: &Context) -> Result<String> {
     serde_json::to_string_pretty(context).context("Failed to serialize context to JSON")
 }
 
+/// Renders comments and timeline events in separate sections.
 pub fn to_markdown(context: &Context) -> String {
+    render_markdown(context, false)
+}
+
+/// Renders comments and timeline events as one stream, oldest first.
+pub fn to_markdown_chronological(context: &Context) -> String {
+    render_markdown(context, true)
+}
+
+fn render_markdown(context: &Context, chronological: bool) -> String {
     let mut md = String::new();
 
     // Title and URL
     md.push_str(&format!("# {}\n\n", context.title));
     md.push_str(&format!("URL: {}\n\n", context.metadata.url));
 
+    // Metadata
+    md.push_str("| Field | Value |\n| --- | --- |\n");
+    for (field, value) in metadata_rows(&context.metadata) {
+        md.push_str(&format!("| {} | {} |\n", field, value.replace('|', "\\|")));
+    }
+    md.push('\n');
+
     // Body
     md.push_str("## Body\n\n");
     md.push_str(&context.body);
     md.push_str("\n\n");
 
-    // Comments
-    md.push_str("## Comments\n\n");
-    for (i, comment) in context.comments.iter().enumerate() {
-        md.push_str(&format!("### Comment {} by {}\n", i + 1, comment.author));
-        if let Some(created_at) = &comment.created_at {
-             md.push_str(&format!("_{}_\n", created_at));
+    // Comments, interleaved with timeline events in chronological mode
+    if chronological {
+        push_chronological_timeline(&mut md, context);
+    } else {
+        md.push_str("## Comments\n\n");
+        for (i, comment) in context.comments.iter().enumerate() {
+            push_comment(&mut md, i + 1, comment);
+        }
+    }
+
+    // Associated pull requests (commits only)
+    if let Some(pulls) = &context.pull_requests {
+        md.push_str("## Pull Requests\n\n");
+        if pulls.is_empty() {
+            md.push_str("_Not part of any pull request._\n");
+        }
+        for pull in pulls {
+            md.push_str(&format!(
+                "- [#{}]({}) {} ({})\n",
+                pull.number, pull.url, pull.title, pull.state
+            ));
         }
         md.push('\n');
-        md.push_str(&comment.body);
-        md.push_str("\n\n---\n\n");
     }
 
-    // Timeline Events (Basic listing)
-    md.push_str("## Timeline Events\n\n");
-    for event in &context.events {
-        if let Some(event_type) = event.get("event").and_then(|v| v.as_str()) {
-            let actor = event.get("actor")
-                .and_then(|a| a.get("login"))
-                .and_then(|v| v.as_str())
-                .unwrap_or("unknown");
-            let created_at = event.get("created_at")
-                .and_then(|v| v.as_str())
-                .unwrap_or("-");
-            
-            md.push_str(&format!("- **{}** by **{}** at {}\n", event_type, actor, created_at));
+    // Commits (PRs only)
+    if let Some(commits) = &context.commits {
+        md.push_str("## Commits\n\n");
+        for commit in commits {
+            let short_sha = commit.sha.get(..7).unwrap_or(&commit.sha);
+            md.push_str(&format!("### `{}` {}\n", short_sha, commit.headline));
+            md.push_str(&format!("_{}", commit.authors.join(", ")));
+            if let Some(authored_at) = &commit.authored_at {
+                md.push_str(&format!(" at {}", authored_at));
+            }
+            md.push_str("_\n\n");
+            // The headline is already in the heading; show the rest of the message.
+            let rest = commit
+                .message
+                .strip_prefix(&commit.headline)
+                .unwrap_or(&commit.message)
+                .trim();
+            if !rest.is_empty() {
+                md.push_str(rest);
+                md.push_str("\n\n");
+            }
+        }
+    }
+
+    // Reviews (PRs only)
+    if let Some(reviews) = &context.reviews {
+        md.push_str("## Reviews\n\n");
+        let verdicts = latest_verdicts(reviews);
+        if verdicts.is_empty() {
+            md.push_str("No reviews submitted.\n\n");
+        } else {
+            md.push_str("Latest verdict per reviewer:\n\n");
+            for review in &verdicts {
+                md.push_str(&format!("- **{}**: {}", review.author, review.state));
+                if let Some(submitted_at) = &review.submitted_at {
+                    md.push_str(&format!(" at {}", submitted_at));
+                }
+                md.push('\n');
+            }
+            md.push('\n');
+        }
+
+        for review in reviews.iter().filter(|r| !r.body.trim().is_empty()) {
+            md.push_str(&format!("### {} by {}\n", review.state, review.author));
+            if let Some(submitted_at) = &review.submitted_at {
+                md.push_str(&format!("_{}_\n", submitted_at));
+            }
+            md.push('\n');
+            md.push_str(&review.body);
+            md.push_str("\n\n---\n\n");
+        }
+    }
+
+    // Review Threads (PRs only), grouped by file
+    if let Some(threads) = &context.review_threads {
+        md.push_str("## Review Threads\n\n");
+        let mut by_file: BTreeMap<&str, Vec<&ReviewThread>> = BTreeMap::new();
+        for thread in threads {
+            by_file.entry(thread.path.as_str()).or_default().push(thread);
+        }
+        for (path, threads) in by_file {
+            md.push_str(&format!("### {}\n\n", path));
+            for thread in threads {
+                push_review_thread(&mut md, thread);
+            }
+        }
+    }
+
+    // CI Checks (opt-in, PRs only)
+    if let Some(checks) = &context.checks {
+        push_checks(&mut md, checks);
+    }
+
+    // Timeline Events (already interleaved in chronological mode)
+    if !chronological {
+        md.push_str("## Timeline Events\n\n");
+        for event in &context.events {
+            push_event(&mut md, event);
+        }
+    }
+
+    // Diff (opt-in, PRs only)
+    if let Some(files) = &context.diff {
+        md.push_str("\n## Diff\n\n");
+        for file in files {
+            let fence = code_fence(&file.patch);
+            md.push_str(&format!("### {}\n\n", file.path));
+            md.push_str(&format!("{}diff\n", fence));
+            md.push_str(&file.patch);
+            if !file.patch.ends_with('\n') {
+                md.push('\n');
+            }
+            md.push_str(&format!("{}\n\n", fence));
+        }
+    }
+
+    // Referenced issues and PRs (opt-in), each rendered one heading level down
+    if !context.references.is_empty() {
+        md.push_str("\n## Referenced Issues and PRs\n\n");
+        for reference in &context.references {
+            let metadata = &reference.context.metadata;
+            md.push_str(&format!(
+                "- [{}#{}]({}): {} ({}, referenced from {})\n",
+                metadata.repo,
+                metadata.number.unwrap_or_default(),
+                metadata.url,
+                reference.context.title,
+                metadata.r#type,
+                reference.referenced_from
+            ));
+        }
+        for reference in &context.references {
+            md.push_str("\n---\n\n");
+            md.push_str(&demote_headings(&render_markdown(
+                &reference.context,
+                chronological,
+            )));
         }
     }
 
     md
 }
+
+// Adds one `#` to every ATX heading outside fenced code blocks, so a whole
+// rendered context can be nested under the parent's sections.
+fn demote_headings(md: &str) -> String {
+    let mut out = String::with_capacity(md.len() + 64);
+    let mut fence: Option<usize> = None;
+
+    for line in md.lines() {
+        let ticks = line.chars().take_while(|&c| c == '`').count();
+        let level = line.chars().take_while(|&c| c == '#').count();
+        match fence {
+            Some(open) if ticks >= open => fence = None,
+            Some(_) => {}
+            None if ticks >= 3 => fence = Some(ticks),
+            None if (1..6).contains(&level) && line[level..].starts_with(' ') => out.push('#'),
+            None => {}
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+
+    out
+}
+
+fn metadata_rows(metadata: &Metadata) -> Vec<(&'static str, String)> {
+    let mut rows = vec![
+        ("Type", metadata.r#type.clone()),
+        ("Repo", metadata.repo.clone()),
+        ("Author", metadata.author.clone()),
+    ];
+
+    if metadata.host != DEFAULT_HOST {
+        rows.insert(2, ("Host", metadata.host.clone()));
+    }
+    if let Some(sha) = &metadata.sha {
+        rows.push(("Commit", sha.clone()));
+    }
+
+    if let Some(state) = &metadata.state {
+        let state = match &metadata.state_reason {
+            Some(reason) => format!("{} ({})", state, reason),
+            None => state.clone(),
+        };
+        rows.push(("State", state));
+    }
+    if let Some(category) = &metadata.category {
+        rows.push(("Category", category.clone()));
+    }
+    if let Some(draft) = metadata.draft {
+        rows.push(("Draft", if draft { "yes" } else { "no" }.to_string()));
+    }
+    if metadata.locked {
+        rows.push(("Locked", "yes".to_string()));
+    }
+    if !metadata.labels.is_empty() {
+        rows.push(("Labels", metadata.labels.join(", ")));
+    }
+    if !metadata.assignees.is_empty() {
+        rows.push(("Assignees", metadata.assignees.join(", ")));
+    }
+
+    let optional = [
+        ("Milestone", &metadata.milestone),
+        ("Base branch", &metadata.base_branch),
+        ("Head branch", &metadata.head_branch),
+        ("Created", &metadata.created_at),
+        ("Updated", &metadata.updated_at),
+        ("Closed", &metadata.closed_at),
+        ("Merged", &metadata.merged_at),
+    ];
+    for (field, value) in optional {
+        if let Some(value) = value {
+            rows.push((field, value.clone()));
+        }
+    }
+
+    rows
+}
+
+fn push_checks(md: &mut String, checks: &Checks) {
+    md.push_str("## CI Checks\n\n");
+    md.push_str(&format!("Head commit: `{}`\n\n", checks.head_sha));
+
+    if checks.check_runs.is_empty() && checks.statuses.is_empty() {
+        md.push_str("No checks reported.\n\n");
+        return;
+    }
+
+    let failing = checks
+        .check_runs
+        .iter()
+        .filter(|r| is_failing_conclusion(r.conclusion.as_deref()))
+        .count()
+        + checks
+            .statuses
+            .iter()
+            .filter(|s| s.state == "failure" || s.state == "error")
+            .count();
+    let pending = checks
+        .check_runs
+        .iter()
+        .filter(|r| r.status != "completed")
+        .count()
+        + checks.statuses.iter().filter(|s| s.state == "pending").count();
+    let total = checks.check_runs.len() + checks.statuses.len();
+    md.push_str(&format!(
+        "{} failing, {} pending, {} total\n\n",
+        failing, pending, total
+    ));
+
+    // Failing runs first so they are not buried under the green ones.
+    let mut runs: Vec<&CheckRun> = checks.check_runs.iter().collect();
+    runs.sort_by_key(|r| !is_failing_conclusion(r.conclusion.as_deref()));
+    for run in runs {
+        let result = run.conclusion.as_deref().unwrap_or(&run.status);
+        md.push_str(&format!("- **{}**: {}", run.name, result));
+        if let Some(url) = &run.url {
+            md.push_str(&format!(" ([details]({}))", url));
+        }
+        md.push('\n');
+        for annotation in &run.annotations {
+            let location = match (annotation.start_line, annotation.end_line) {
+                (Some(start), Some(end)) if start != end => {
+                    format!("{}:{}-{}", annotation.path, start, end)
+                }
+                (Some(line), _) => format!("{}:{}", annotation.path, line),
+                _ => annotation.path.clone(),
+            };
+            md.push_str(&format!("  - `{}` [{}]", location, annotation.level));
+            if let Some(title) = &annotation.title {
+                md.push_str(&format!(" {}:", title));
+            }
+            md.push_str(&format!(" {}\n", annotation.message.replace('\n', " ")));
+        }
+        if let Some(log) = &run.log_excerpt {
+            let fence = code_fence(log);
+            md.push_str(&format!("\n**{} log excerpt**\n\n", run.name));
+            md.push_str(&format!("{}text\n{}\n{}\n\n", fence, log, fence));
+        }
+    }
+
+    if !checks.statuses.is_empty() {
+        md.push_str("\n### Commit Statuses\n\n");
+        for status in &checks.statuses {
+            md.push_str(&format!("- **{}**: {}", status.context, status.state));
+            if let Some(description) = &status.description {
+                md.push_str(&format!(" - {}", description));
+            }
+            if let Some(url) = &status.url {
+                md.push_str(&format!(" ([details]({}))", url));
+            }
+            md.push('\n');
+        }
+    }
+    md.push('\n');
+}
+
+// A plain comment review doesn't withdraw an earlier approval or change
+// request, so it only counts when the reviewer has nothing stronger on record.
+// Reviewers keep the order of their first review.
+fn latest_verdicts(reviews: &[Review]) -> Vec<&Review> {
+    let mut verdicts: Vec<&Review> = Vec::new();
+    for review in reviews {
+        match verdicts.iter_mut().find(|v| v.author == review.author) {
+            Some(current) => {
+                if review.state != "COMMENTED" || current.state == "COMMENTED" {
+                    *current = review;
+                }
+            }
+            None => verdicts.push(review),
+        }
+    }
+    verdicts
+}
+
+fn push_review_thread(md: &mut String, thread: &ReviewThread) {
+    let mut heading = match (thread.start_line, thread.line) {
+        (Some(start), Some(end)) if start != end => format!("Lines {}-{}", start, end),
+        (_, Some(line)) => format!("Line {}", line),
+        _ => match (thread.original_start_line, thread.original_line) {
+            (Some(start), Some(end)) if start != end => {
+                format!("Originally lines {}-{}", start, end)
+            }
+            (_, Some(line)) => format!("Originally line {}", line),
+            _ => "File comment".to_string(),
+        },
+    };
+    let mut states = Vec::new();
+    if thread.is_resolved {
+        states.push("resolved");
+    }
+    if thread.is_outdated {
+        states.push("outdated");
+    }
+    if !states.is_empty() {
+        heading.push_str(&format!(" ({})", states.join(", ")));
+    }
+    md.push_str(&format!("#### {}\n\n", heading));
+
+    if !thread.diff_hunk.is_empty() {
+        let fence = code_fence(&thread.diff_hunk);
+        md.push_str(&format!("{}diff\n{}\n{}\n\n", fence, thread.diff_hunk.trim_end(), fence));
+    }
+
+    for (i, comment) in thread.comments.iter().enumerate() {
+        let label = if i == 0 { "Comment" } else { "Reply" };
+        md.push_str(&format!("**{} by {}**", label, comment.author));
+        if let Some(created_at) = &comment.created_at {
+            md.push_str(&format!(" _{}_", created_at));
+        }
+        md.push_str("\n\n");
+        md.push_str(&comment.body);
+        md.push_str("\n\n");
+    }
+    md.push_str("---\n\n");
+}
+
+fn push_comment(md: &mut String, number: usize, comment: &UnifiedComment) {
+    let answer = if comment.is_answer { " (accepted answer)" } else { "" };
+    md.push_str(&format!("### Comment {} by {}{}\n", number, comment.author, answer));
+    if let Some(created_at) = &comment.created_at {
+        md.push_str(&format!("_{}_\n", created_at));
+    }
+    md.push('\n');
+    md.push_str(&comment.body);
+    md.push_str("\n\n");
+
+    // Discussion replies stay under the comment they answer.
+    for (i, reply) in comment.replies.iter().enumerate() {
+        md.push_str(&format!("#### Reply {}.{} by {}\n", number, i + 1, reply.author));
+        if let Some(created_at) = &reply.created_at {
+            md.push_str(&format!("_{}_\n", created_at));
+        }
+        md.push('\n');
+        md.push_str(&reply.body);
+        md.push_str("\n\n");
+    }
+    md.push_str("---\n\n");
+}
+
+fn push_event(md: &mut String, event: &TimelineEvent) {
+    md.push_str(&format!(
+        "- **{}** {} at {}\n",
+        event.actor,
+        describe_event(&event.kind),
+        event.created_at.as_deref().unwrap_or("-")
+    ));
+}
+
+enum Entry<'a> {
+    Comment(usize, &'a UnifiedComment),
+    Event(&'a TimelineEvent),
+}
+
+// GitHub timestamps are all RFC 3339 in UTC, so they order as strings. The
+// sort is stable: ties keep comments before events, and undated entries go last.
+// `commented` events are dropped, as each repeats a comment already listed.
+fn push_chronological_timeline(md: &mut String, context: &Context) {
+    let mut entries: Vec<(Option<&str>, Entry)> = context
+        .comments
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (c.created_at.as_deref(), Entry::Comment(i + 1, c)))
+        .chain(
+            context
+                .events
+                .iter()
+                .filter(|e| !matches!(e.kind, EventKind::Commented))
+                .map(|e| (e.created_at.as_deref(), Entry::Event(e))),
+        )
+        .collect();
+    entries.sort_by_key(|(created_at, _)| (created_at.is_none(), *created_at));
+
+    md.push_str("## Timeline\n\n");
+    let mut in_event_list = false;
+    for (_, entry) in entries {
+        match entry {
+            Entry::Comment(number, comment) => {
+                // A list directly above a heading needs a blank line to end it.
+                if in_event_list {
+                    md.push('\n');
+                    in_event_list = false;
+                }
+                push_comment(md, number, comment);
+            }
+            Entry::Event(event) => {
+                push_event(md, event);
+                in_event_list = true;
+            }
+        }
+    }
+    if in_event_list {
+        md.push('\n');
+    }
+}
+
+// What the actor did, e.g. "added label `bug`".
+fn describe_event(kind: &EventKind) -> String {
+    let short = |sha: &str| sha.get(..7).unwrap_or(sha).to_string();
+    let in_commit = |commit: &Option<String>| match commit {
+        Some(sha) => format!(" in `{}`", short(sha)),
+        None => String::new(),
+    };
+
+    match kind {
+        EventKind::Labeled { label } => format!("added label `{}`", label),
+        EventKind::Unlabeled { label } => format!("removed label `{}`", label),
+        EventKind::Assigned { assignee } => format!("assigned **{}**", assignee),
+        EventKind::Unassigned { assignee } => format!("unassigned **{}**", assignee),
+        EventKind::Renamed { from, to } => {
+            format!("changed the title from \"{}\" to \"{}\"", from, to)
+        }
+        EventKind::Closed {
+            commit,
+            state_reason,
+        } => {
+            let reason = match state_reason {
+                Some(reason) => format!(" as {}", reason.replace('_', " ")),
+                None => String::new(),
+            };
+            format!("closed this{}{}", reason, in_commit(commit))
+        }
+        EventKind::Reopened => "reopened this".to_string(),
+        EventKind::Referenced { commit } => format!("referenced this{}", in_commit(commit)),
+        EventKind::CrossReferenced { source } => format!(
+            "mentioned this in [{}#{}]({}): {}",
+            source.repo, source.number, source.url, source.title
+        ),
+        EventKind::Merged { commit } => format!("merged this{}", in_commit(commit)),
+        EventKind::ReviewRequested { reviewer } => {
+            format!("requested a review from **{}**", reviewer)
+        }
+        EventKind::ReviewRequestRemoved { reviewer } => {
+            format!("removed the review request for **{}**", reviewer)
+        }
+        EventKind::Milestoned { milestone } => {
+            format!("added this to the **{}** milestone", milestone)
+        }
+        EventKind::Demilestoned { milestone } => {
+            format!("removed this from the **{}** milestone", milestone)
+        }
+        EventKind::Locked { lock_reason } => match lock_reason {
+            Some(reason) => format!("locked this as {}", reason.replace('_', " ")),
+            None => "locked this".to_string(),
+        },
+        EventKind::Unlocked => "unlocked this".to_string(),
+        EventKind::ReadyForReview => "marked this ready for review".to_string(),
+        EventKind::ConvertToDraft => "converted this to a draft".to_string(),
+        EventKind::HeadRefForcePushed => "force-pushed the head branch".to_string(),
+        EventKind::Commented => "commented".to_string(),
+        EventKind::Committed { commit } => match commit {
+            Some(sha) => format!("added commit `{}`", short(sha)),
+            None => "added a commit".to_string(),
+        },
+        EventKind::Reviewed { state } => match state.as_deref() {
+            Some("approved") => "approved these changes".to_string(),
+            Some("changes_requested") => "requested changes".to_string(),
+            Some("dismissed") => "reviewed this (dismissed)".to_string(),
+            _ => "reviewed this".to_string(),
+        },
+        EventKind::Other { event } => format!("`{}`", event),
+    }
+}
+
+// Patches often contain markdown with their own fences, so use one longer
+// than any backtick run in the content.
+fn code_fence(content: &str) -> String {
+    let longest = content
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
@@ -1,80 +1,234 @@
-use crate::types::{Context, GhResponse, Metadata, UnifiedComment};
+use crate::backend::{flatten_pages, Backend};
+use crate::diff::{split_patch, DiffFilter};
+use crate::error::{invalid_args, ErrorKind};
+use crate::graphql;
+use crate::logs;
+use crate::refs::{self, FollowRefs};
+use crate::types::{
+    is_failing_conclusion, CheckAnnotation, CheckRun, Checks, Commit, CommitStatus, Context, EventKind, FileDiff,
+    IssueReference, Metadata, PullRequestLink, Review, ReviewThread, TimelineEvent, UnifiedComment,
+    DEFAULT_HOST,
+};
+use crate::wire::{
+    GhCheckAnnotation, GhCheckRunsPage, GhCombinedStatus, GhComment, GhCommit, GhCommitComment,
+    GhCommitDetail, GhPullSummary, GhReviewThread, GhTimelineEvent,
+};
 use anyhow::{anyhow, Context as _, Result};
-use std::process::Command;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum TargetType {
     Issue,
     Pr,
+    Discussion,
+    Commit { sha: String },
 }
 
 #[derive(Debug)]
 pub struct Target {
+    pub host: String,
     pub owner: String,
     pub repo: String,
-    pub number: u64,
+    /// `None` for commits, which are identified by `TargetType::Commit`'s sha.
+    pub number: Option<u64>,
     pub kind: TargetType,
 }
 
-#[derive(Debug, serde::Deserialize)]
-struct IssueListItem {
-    pub number: u64,
+impl Target {
+    /// The number of an issue, PR or discussion; an error for commits.
+    pub fn item_number(&self) -> Result<u64> {
+        self.number.ok_or_else(|| {
+            anyhow!(
+                "{}/{} commit has no issue, PR or discussion number",
+                self.owner,
+                self.repo
+            )
+        })
+    }
+}
+
+/// Optional, slower parts of a context. Everything is off by default.
+#[derive(Debug, Default, Clone)]
+pub struct FetchOptions {
+    /// Include the PR diff, keeping only files accepted by the filter.
+    pub diff: Option<DiffFilter>,
+    /// Include check runs and commit statuses for the PR head.
+    pub checks: bool,
+    /// With `checks`, attach a log excerpt of at most this many bytes to
+    /// each failing GitHub Actions job.
+    pub job_log_limit: Option<usize>,
+    /// Fetch the item, its comments, timeline and reviews with a single
+    /// GraphQL query instead of one call per resource.
+    pub graphql: bool,
+    /// Also fetch the issues and PRs the item references, within limits.
+    pub follow_refs: Option<FollowRefs>,
+}
+impl FetchOptions {
+    pub fn builder() -> FetchOptionsBuilder {
+        FetchOptionsBuilder::default()
+    }
+}
+
+/// Builds [`FetchOptions`], checking that they fit together.
+///
+/// ```
+/// let options = gh_context::FetchOptions::builder()
+///     .checks(true)
+///     .job_logs(4000)
+///     .follow_refs(1, 20)
+///     .build()
+///     .unwrap();
+/// assert!(options.checks);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct FetchOptionsBuilder {
+    options: FetchOptions,
+}
+
+impl FetchOptionsBuilder {
+    /// Includes PR diffs, keeping only files accepted by `filter`.
+    pub fn diff(mut self, filter: DiffFilter) -> Self {
+        self.options.diff = Some(filter);
+        self
+    }
+
+    pub fn checks(mut self, checks: bool) -> Self {
+        self.options.checks = checks;
+        self
+    }
+
+    /// Attaches a log excerpt of at most `limit` bytes to each failing
+    /// GitHub Actions job. Requires `checks`.
+    pub fn job_logs(mut self, limit: usize) -> Self {
+        self.options.job_log_limit = Some(limit);
+        self
+    }
+
+    pub fn graphql(mut self, graphql: bool) -> Self {
+        self.options.graphql = graphql;
+        self
+    }
+
+    /// Follows references up to `depth` hops away, fetching at most
+    /// `max_items` referenced items in total.
+    pub fn follow_refs(mut self, depth: u32, max_items: usize) -> Self {
+        self.options.follow_refs = Some(FollowRefs { depth, max_items });
+        self
+    }
+
+    pub fn build(self) -> Result<FetchOptions> {
+        let options = self.options;
+        if let Some(limit) = options.job_log_limit {
+            if !options.checks {
+                return Err(invalid_args("Job logs require checks"));
+            }
+            if limit < 256 {
+                return Err(invalid_args("The job log limit must be at least 256 bytes"));
+            }
+        }
+        if let Some(follow) = &options.follow_refs {
+            if follow.depth == 0 {
+                return Err(invalid_args("The reference depth must be at least 1"));
+            }
+            if follow.max_items == 0 {
+                return Err(invalid_args("The referenced item limit must be at least 1"));
+            }
+        }
+        Ok(options)
+    }
 }
 
+/// Parses a GitHub URL or shorthand (`owner/repo#123`, `owner/repo@sha`)
+/// into a target on github.com, unless the URL names another host.
+/// `owner/repo#123` is ambiguous and needs `force_issue` or `force_pr`.
 pub fn parse_target(input: &str, force_issue: bool, force_pr: bool) -> Result<Target> {
     if force_issue && force_pr {
-        return Err(anyhow!("Cannot specify both --issue and --pr"));
+        return Err(invalid_args("Cannot specify both --issue and --pr"));
     }
 
-    // case 1: Full URL
-    if input.starts_with("https://github.com/") {
-        let parts: Vec<&str> = input
-            .trim_start_matches("https://github.com/")
-            .split('/')
-            .collect();
+    // case 1: Full URL, on github.com or a GitHub Enterprise Server host
+    if let Some((host, path)) = split_url(input) {
+        let parts: Vec<&str> = path.split('/').collect();
         if parts.len() < 4 {
-            return Err(anyhow!("Invalid GitHub URL format"));
+            return Err(invalid_args("Invalid GitHub URL format"));
         }
         let owner = parts[0].to_string();
         let repo = parts[1].to_string();
         let kind_str = parts[2];
         let number_str = parts[3];
 
+        // `/commit/<sha>`, or a commit viewed inside a PR: `/pull/<n>/commits/<sha>`
+        let commit_sha = match (kind_str, parts.get(4), parts.get(5)) {
+            ("commit", _, _) => Some(number_str),
+            ("pull", Some(&"commits"), Some(sha)) => Some(*sha),
+            _ => None,
+        };
+        if let Some(sha) = commit_sha {
+            return Ok(Target {
+                host,
+                owner,
+                repo,
+                number: None,
+                kind: TargetType::Commit {
+                    sha: parse_sha(sha.split(['#', '?']).next().unwrap_or(""))?,
+                },
+            });
+        }
+
         let kind = if kind_str == "issues" {
             TargetType::Issue
         } else if kind_str == "pull" {
             TargetType::Pr
+        } else if kind_str == "discussions" {
+            TargetType::Discussion
         } else {
-            return Err(anyhow!("URL must contain 'issues' or 'pull'"));
+            return Err(invalid_args("URL must contain 'issues', 'pull' or 'discussions'"));
         };
 
         let number = number_str
             .split('#').next().unwrap()
             .split('?').next().unwrap()
             .parse::<u64>()
-            .context("Failed to parse issue/pr number from URL")?;
+            .map_err(|err| invalid_args(format!("Failed to parse issue/pr number from URL: {}", err)))?;
 
         return Ok(Target {
+            host,
             owner,
             repo,
-            number,
+            number: Some(number),
             kind,
         });
     }
 
-    // case 2: Shorthand owner/repo#number
+    // case 2: Commit shorthand owner/repo@sha
+    if let Some((repo_part, sha)) = input.split_once('@') {
+        let parts: Vec<&str> = repo_part.split('/').collect();
+        if parts.len() != 2 {
+            return Err(invalid_args("Commit shorthand must be in format owner/repo@sha"));
+        }
+        return Ok(Target {
+            host: DEFAULT_HOST.to_string(),
+            owner: parts[0].to_string(),
+            repo: parts[1].to_string(),
+            number: None,
+            kind: TargetType::Commit {
+                sha: parse_sha(sha)?,
+            },
+        });
+    }
+
+    // case 3: Shorthand owner/repo#number
     // We also support owner/repo issue_number if that's common, but strictly owner/repo#number is requested.
     // Actually, user said: <owner>/<repo>#<number>
     if let Some((repo_part, number_part)) = input.split_once('#') {
         let parts: Vec<&str> = repo_part.split('/').collect();
         if parts.len() != 2 {
-            return Err(anyhow!("Shorthand must be in format owner/repo#number"));
+            return Err(invalid_args("Shorthand must be in format owner/repo#number"));
         }
         let owner = parts[0].to_string();
         let repo = parts[1].to_string();
         let number = number_part
             .parse::<u64>()
-            .context("Failed to parse number from shorthand")?;
+            .map_err(|err| invalid_args(format!("Failed to parse number from shorthand: {}", err)))?;
         
         // Disambiguation
         let kind = if force_pr {
@@ -83,36 +237,48 @@ pub fn parse_target(input: &str, force_issue: bool, force_pr: bool) -> Result<Ta
             TargetType::Issue
         } else {
              // If ambiguous, require --issue or --pr as per spec
-             return Err(anyhow!("Ambiguous shorthand. Please specify --issue or --pr"));
+             return Err(invalid_args("Ambiguous shorthand. Please specify --issue or --pr"));
         };
 
         return Ok(Target {
+            host: DEFAULT_HOST.to_string(),
             owner,
             repo,
-            number,
+            number: Some(number),
             kind,
         });
     }
 
-    Err(anyhow!("Invalid input format. Must be a GitHub URL or owner/repo#number shorthand"))
+    Err(invalid_args(
+        "Invalid input format. Must be a GitHub URL, owner/repo#number or owner/repo@sha shorthand"
+    ))
 }
 
+// Abbreviated (at least 7 characters) or full hex commit SHA.
+fn parse_sha(sha: &str) -> Result<String> {
+    if (7..=40).contains(&sha.len()) && sha.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(sha.to_ascii_lowercase())
+    } else {
+        Err(invalid_args(format!("Invalid commit SHA: {}", sha)))
+    }
+}
+
+/// Parses `owner/repo` or a repo URL into its owner and name.
 pub fn parse_repo(input: &str) -> Result<(String, String)> {
     if input.contains('#') {
-        return Err(anyhow!("Repo input must not include an issue/pr number"));
+        return Err(invalid_args("Repo input must not include an issue/pr number"));
     }
 
-    let base = if let Some(rest) = input.strip_prefix("https://github.com/") {
-        rest
-    } else {
-        input
+    let base = match split_url(input) {
+        Some((_, path)) => path,
+        None => input,
     };
-    let trimmed = base.split(|c| c == '?' || c == '#').next().unwrap_or("");
+    let trimmed = base.split(['?', '#']).next().unwrap_or("");
 
     let path = trimmed.trim_matches('/');
     let parts: Vec<&str> = path.split('/').collect();
     if parts.len() < 2 {
-        return Err(anyhow!("Repo input must be in format owner/repo"));
+        return Err(invalid_args("Repo input must be in format owner/repo"));
     }
 
     let owner = parts[0].to_string();
@@ -122,145 +288,592 @@ pub fn parse_repo(input: &str) -> Result<(String, String)> {
         let segment = parts[2];
         if segment == "issues" {
             if parts.len() > 3 {
-                return Err(anyhow!("Bulk issues URL should not include an issue number"));
+                return Err(invalid_args("Bulk issues URL should not include an issue number"));
             }
         } else if segment == "pull" || segment == "pulls" {
-            return Err(anyhow!("Bulk mode supports issues only; use an /issues URL"));
+            return Err(invalid_args("Bulk mode supports issues only; use an /issues URL"));
         } else {
-            return Err(anyhow!("Invalid repo URL format"));
+            return Err(invalid_args("Invalid repo URL format"));
         }
     }
 
     Ok((owner, repo))
 }
 
-pub fn fetch_context(target: &Target) -> Result<Context> {
-    let repo_arg = format!("{}/{}", target.owner, target.repo);
-    let num_arg = target.number.to_string();
+/// Host of a URL input such as `https://github.example.com/owner/repo/issues/1`,
+/// or `None` for shorthand input.
+pub fn parse_host(input: &str) -> Option<String> {
+    split_url(input).map(|(host, _)| host)
+}
 
-    let (subcommand, kind_str) = match target.kind {
-        TargetType::Issue => ("issue", "issue"),
-        TargetType::Pr => ("pr", "pr"),
+// Splits `https://<host>/<path>` into a normalized host and the path.
+fn split_url(input: &str) -> Option<(String, &str)> {
+    let rest = input
+        .strip_prefix("https://")
+        .or_else(|| input.strip_prefix("http://"))?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    let host = host.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").map(str::to_string).unwrap_or(host);
+    Some((host, path))
+}
+
+/// Fetches everything about `target` into a [`Context`], plus the optional
+/// parts that `options` turn on.
+pub fn fetch_context(
+    backend: &dyn Backend,
+    target: &Target,
+    options: &FetchOptions,
+) -> Result<Context> {
+    let mut context = match &target.kind {
+        TargetType::Commit { sha } => fetch_commit(backend, target, sha, options)?,
+        _ => fetch_item(backend, target, options)?,
     };
 
-    // gh <subcommand> view <number> --repo <owner>/<repo> --comments --json title,body,url,author,comments
-    let output = Command::new("gh")
-        .arg(subcommand)
-        .arg("view")
-        .arg(&num_arg)
-        .arg("--repo")
-        .arg(&repo_arg)
-        .arg("--comments")
-        .arg("--json")
-        .arg("title,body,url,author,comments,number")
-        .output()
-        .context("Failed to execute 'gh' command. Is it installed?")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("'gh' command failed: {}", stderr));
+    if let Some(follow) = &options.follow_refs {
+        let fetch = |target: &Target, options: &FetchOptions| fetch_context(backend, target, options);
+        refs::expand(backend, target, &mut context, follow, options, &fetch)?;
     }
 
-    let gh_data: GhResponse = serde_json::from_slice(&output.stdout)
-        .context("Failed to parse JSON output from 'gh'")?;
+    Ok(context)
+}
+
+// An issue, PR or discussion.
+fn fetch_item(backend: &dyn Backend, target: &Target, options: &FetchOptions) -> Result<Context> {
+    let repo_arg = format!("{}/{}", target.owner, target.repo);
+    let number = target.item_number()?;
+
+    let kind_str = match target.kind {
+        TargetType::Issue => "issue",
+        TargetType::Pr => "pr",
+        TargetType::Discussion => "discussion",
+        TargetType::Commit { .. } => unreachable!("commits are fetched by fetch_commit"),
+    };
+
+    // Discussions are only exposed through GraphQL.
+    let use_graphql = options.graphql || target.kind == TargetType::Discussion;
+    let (gh_data, events, raw_threads) = if use_graphql {
+        let item = graphql::fetch_item(backend, target)?;
+        (item.response, item.events, item.review_threads)
+    } else {
+        let gh_data = backend.view(target)?;
+        // Only a missing timeline leaves the events empty; other failures
+        // would make the context quietly incomplete.
+        let events = match backend.fetch_timeline(target) {
+            Ok(events) => events,
+            Err(err) if ErrorKind::of(&err) == ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        let raw_threads = match target.kind {
+            TargetType::Pr => graphql::fetch_review_threads(backend, target)?,
+            _ => Vec::new(),
+        };
+        (gh_data, events, raw_threads)
+    };
 
     // Convert to unified Context
-    let comments: Vec<UnifiedComment> = gh_data
-        .comments
+    let comments: Vec<UnifiedComment> = gh_data.comments.into_iter().map(to_comment).collect();
+
+    // Items that are not JSON objects are dropped; unknown kinds become `EventKind::Other`.
+    let events: Vec<TimelineEvent> = events
         .into_iter()
-        .map(|c| UnifiedComment {
-            author: c.author.map(|a| a.login).unwrap_or_else(|| "ghost".to_string()),
-            body: c.body,
-            created_at: c.created_at,
-        })
+        .filter_map(|e| serde_json::from_value::<GhTimelineEvent>(e).ok())
+        .map(to_timeline_event)
         .collect();
 
     let author_login = gh_data.author.map(|a| a.login).unwrap_or_else(|| "unknown".to_string());
 
-    let events = fetch_timeline(target).unwrap_or_else(|_| Vec::new());
+    let commits = match target.kind {
+        TargetType::Pr => Some(gh_data.commits.into_iter().map(to_commit).collect()),
+        _ => None,
+    };
+
+    let reviews = match target.kind {
+        TargetType::Pr => Some(
+            gh_data
+                .reviews
+                .into_iter()
+                .filter(|r| r.state != "PENDING")
+                .map(|r| Review {
+                    author: r.author.map(|a| a.login).unwrap_or_else(|| "ghost".to_string()),
+                    state: r.state,
+                    submitted_at: r.submitted_at,
+                    body: r.body,
+                })
+                .collect(),
+        ),
+        _ => None,
+    };
+
+    // `gh ... view --json` has no lock field; the latest lock event tells us.
+    let locked = gh_data.locked.unwrap_or_else(|| {
+        events
+            .iter()
+            .rev()
+            .find_map(|e| match e.kind {
+                EventKind::Locked { .. } => Some(true),
+                EventKind::Unlocked => Some(false),
+                _ => None,
+            })
+            .unwrap_or(false)
+    });
+
+    let review_threads = match target.kind {
+        TargetType::Pr => Some(raw_threads.into_iter().map(to_review_thread).collect()),
+        _ => None,
+    };
+
+    let checks = match (&target.kind, &gh_data.head_ref_oid) {
+        (TargetType::Pr, Some(sha)) if options.checks => {
+            Some(fetch_checks(backend, target, sha, options)?)
+        }
+        _ => None,
+    };
+
+    let diff = match (&target.kind, &options.diff) {
+        (TargetType::Pr, Some(filter)) => {
+            let endpoint = format!("repos/{}/pulls/{}", repo_arg, number);
+            Some(fetch_diff(backend, &endpoint, filter)?)
+        }
+        _ => None,
+    };
 
-    let context = Context {
+    Ok(Context {
         metadata: Metadata {
+            host: target.host.clone(),
             repo: repo_arg,
-            number: target.number,
+            number: Some(number),
+            sha: None,
             r#type: kind_str.to_string(),
             url: gh_data.url,
             author: author_login,
+            state: gh_data.state,
+            state_reason: gh_data.state_reason.filter(|r| !r.is_empty()),
+            draft: gh_data.is_draft,
+            labels: gh_data.labels.into_iter().map(|l| l.name).collect(),
+            assignees: gh_data.assignees.into_iter().map(|a| a.login).collect(),
+            milestone: gh_data.milestone.map(|m| m.title),
+            created_at: gh_data.created_at,
+            updated_at: gh_data.updated_at,
+            closed_at: gh_data.closed_at,
+            merged_at: gh_data.merged_at,
+            base_branch: gh_data.base_ref_name,
+            head_branch: gh_data.head_ref_name,
+            category: gh_data.category.map(|c| c.name),
+            locked,
         },
         title: gh_data.title,
         body: gh_data.body,
         comments,
         events,
+        commits,
+        reviews,
+        review_threads,
+        checks,
+        diff,
+        pull_requests: None,
+        references: Vec::new(),
+    })
+}
+
+// A commit with its message, diff, commit comments and the PRs it belongs to.
+// The diff is always included; `--diff-include`/`--diff-exclude` still apply.
+fn fetch_commit(
+    backend: &dyn Backend,
+    target: &Target,
+    sha: &str,
+    options: &FetchOptions,
+) -> Result<Context> {
+    let repo_arg = format!("{}/{}", target.owner, target.repo);
+    let endpoint = format!("repos/{}/commits/{}", repo_arg, sha);
+
+    let commit: GhCommitDetail = serde_json::from_value(backend.rest(&endpoint)?)
+        .context("Failed to parse commit from GitHub")?;
+    let comments_endpoint = format!("{}/comments?per_page=100", endpoint);
+    let raw_comments: Vec<GhCommitComment> =
+        serde_json::from_value(flatten_pages(backend.rest_pages(&comments_endpoint)?)?.into())
+            .context("Failed to parse commit comments from GitHub")?;
+    let pulls_endpoint = format!("{}/pulls?per_page=100", endpoint);
+    let pulls: Vec<GhPullSummary> =
+        serde_json::from_value(flatten_pages(backend.rest_pages(&pulls_endpoint)?)?.into())
+            .context("Failed to parse pull requests for commit from GitHub")?;
+
+    let (title, body) = match commit.commit.message.split_once('\n') {
+        Some((headline, rest)) => (headline.to_string(), rest.trim().to_string()),
+        None => (commit.commit.message.clone(), String::new()),
+    };
+    let git_author = commit.commit.author.as_ref();
+    let author = match (&commit.author, git_author) {
+        (Some(user), _) => user.login.clone(),
+        (None, Some(author)) => author.name.clone(),
+        (None, None) => "unknown".to_string(),
     };
 
-    Ok(context)
+    // Line comments name the file and line they were left on.
+    let comments = raw_comments
+        .into_iter()
+        .map(|c| {
+            let location = match (&c.path, c.line) {
+                (Some(path), Some(line)) => format!("_On `{}` line {}_\n\n", path, line),
+                (Some(path), None) => format!("_On `{}`_\n\n", path),
+                _ => String::new(),
+            };
+            UnifiedComment {
+                author: c.user.map(|u| u.login).unwrap_or_else(|| "ghost".to_string()),
+                body: format!("{}{}", location, c.body),
+                created_at: c.created_at,
+                is_answer: false,
+                replies: Vec::new(),
+            }
+        })
+        .collect();
+
+    let pull_requests = pulls
+        .into_iter()
+        .map(|p| PullRequestLink {
+            number: p.number,
+            title: p.title,
+            url: p.html_url,
+            state: if p.merged_at.is_some() {
+                "merged".to_string()
+            } else {
+                p.state
+            },
+        })
+        .collect();
+
+    let checks = if options.checks {
+        Some(fetch_checks(backend, target, &commit.sha, options)?)
+    } else {
+        None
+    };
+
+    let filter = options.diff.clone().unwrap_or_default();
+    let diff = fetch_diff(backend, &endpoint, &filter)?;
+
+    Ok(Context {
+        metadata: Metadata {
+            host: target.host.clone(),
+            repo: repo_arg,
+            number: None,
+            sha: Some(commit.sha),
+            r#type: "commit".to_string(),
+            url: commit.html_url,
+            author,
+            state: None,
+            state_reason: None,
+            draft: None,
+            labels: Vec::new(),
+            assignees: Vec::new(),
+            milestone: None,
+            created_at: git_author.and_then(|a| a.date.clone()),
+            updated_at: None,
+            closed_at: None,
+            merged_at: None,
+            base_branch: None,
+            head_branch: None,
+            category: None,
+            locked: false,
+        },
+        title,
+        body,
+        comments,
+        events: Vec::new(),
+        commits: None,
+        reviews: None,
+        review_threads: None,
+        checks,
+        diff: Some(diff),
+        pull_requests: Some(pull_requests),
+        references: Vec::new(),
+    })
 }
 
+/// Most issues a bulk run may list (`per_page * pages`).
+pub const MAX_BULK_ITEMS: u64 = 1000;
+
 pub fn list_issue_numbers(
+    backend: &dyn Backend,
     repo: &str,
     state: &str,
     per_page: u32,
     pages: u32,
 ) -> Result<Vec<u64>> {
     let limit = (per_page as u64) * (pages as u64);
-    const MAX_ITEMS: u64 = 1000;
-    if limit > MAX_ITEMS {
-        return Err(anyhow!(
-            "Requested {} items (per_page * pages) exceeds maximum allowed of {}",
-            limit,
-            MAX_ITEMS
-        ));
+    backend.list_issue_numbers(repo, state, limit)
+}
+
+fn to_commit(raw: GhCommit) -> Commit {
+    let authors = raw
+        .authors
+        .into_iter()
+        .map(|a| if a.login.is_empty() { a.name } else { a.login })
+        .collect();
+
+    let message = if raw.message_body.is_empty() {
+        raw.message_headline.clone()
+    } else {
+        format!("{}\n\n{}", raw.message_headline, raw.message_body)
+    };
+
+    Commit {
+        sha: raw.oid,
+        authors,
+        authored_at: raw.authored_date,
+        headline: raw.message_headline,
+        message,
+    }
+}
+
+fn to_comment(raw: GhComment) -> UnifiedComment {
+    UnifiedComment {
+        author: raw.author.map(|a| a.login).unwrap_or_else(|| "ghost".to_string()),
+        body: raw.body,
+        created_at: raw.created_at,
+        is_answer: raw.is_answer,
+        replies: raw.replies.into_iter().map(to_comment).collect(),
     }
+}
+
+pub(crate) fn to_timeline_event(raw: GhTimelineEvent) -> TimelineEvent {
+    let label = || raw.label.as_ref().map(|l| l.name.clone()).unwrap_or_default();
+    let assignee = || raw.assignee.as_ref().map(|a| a.login.clone()).unwrap_or_default();
+    let milestone = || raw.milestone.as_ref().map(|m| m.title.clone()).unwrap_or_default();
+    // Teams are shown by name; users by login.
+    let reviewer = || match (&raw.requested_reviewer, &raw.requested_team) {
+        (Some(user), _) => user.login.clone(),
+        (None, Some(team)) => format!("team {}", team.name),
+        (None, None) => "unknown".to_string(),
+    };
+
+    let kind = match raw.event.as_str() {
+        "labeled" => EventKind::Labeled { label: label() },
+        "unlabeled" => EventKind::Unlabeled { label: label() },
+        "assigned" => EventKind::Assigned { assignee: assignee() },
+        "unassigned" => EventKind::Unassigned { assignee: assignee() },
+        "renamed" => {
+            let (from, to) = raw
+                .rename
+                .as_ref()
+                .map(|r| (r.from.clone(), r.to.clone()))
+                .unwrap_or_default();
+            EventKind::Renamed { from, to }
+        }
+        "closed" => EventKind::Closed {
+            commit: raw.commit_id.clone(),
+            state_reason: raw.state_reason.clone(),
+        },
+        "reopened" => EventKind::Reopened,
+        "referenced" => EventKind::Referenced {
+            commit: raw.commit_id.clone(),
+        },
+        "cross-referenced" => match raw.source.as_ref().and_then(|s| s.issue.as_ref()) {
+            Some(issue) => EventKind::CrossReferenced {
+                source: IssueReference {
+                    repo: issue
+                        .repository
+                        .as_ref()
+                        .map(|r| r.full_name.clone())
+                        .unwrap_or_default(),
+                    number: issue.number,
+                    title: issue.title.clone(),
+                    url: issue.html_url.clone(),
+                },
+            },
+            None => EventKind::Other {
+                event: raw.event.clone(),
+            },
+        },
+        "merged" => EventKind::Merged {
+            commit: raw.commit_id.clone(),
+        },
+        "review_requested" => EventKind::ReviewRequested { reviewer: reviewer() },
+        "review_request_removed" => EventKind::ReviewRequestRemoved { reviewer: reviewer() },
+        "milestoned" => EventKind::Milestoned { milestone: milestone() },
+        "demilestoned" => EventKind::Demilestoned { milestone: milestone() },
+        "locked" => EventKind::Locked {
+            lock_reason: raw.lock_reason.clone(),
+        },
+        "unlocked" => EventKind::Unlocked,
+        "ready_for_review" => EventKind::ReadyForReview,
+        "convert_to_draft" => EventKind::ConvertToDraft,
+        "head_ref_force_pushed" => EventKind::HeadRefForcePushed,
+        "commented" => EventKind::Commented,
+        "committed" => EventKind::Committed {
+            commit: raw.sha.clone(),
+        },
+        "reviewed" => EventKind::Reviewed {
+            state: raw.state.clone(),
+        },
+        _ => EventKind::Other {
+            event: raw.event.clone(),
+        },
+    };
 
-    let output = Command::new("gh")
-        .arg("issue")
-        .arg("list")
-        .arg("--repo")
-        .arg(repo)
-        .arg("--state")
-        .arg(state)
-        .arg("--limit")
-        .arg(limit.to_string())
-        .arg("--json")
-        .arg("number")
-        .output()
-        .context("Failed to execute 'gh issue list'")?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow!("'gh issue list' failed: {}", stderr));
+    let actor = raw
+        .actor
+        .or(raw.user)
+        .map(|a| a.login)
+        .or_else(|| raw.author.as_ref().map(|a| a.name.clone()))
+        .unwrap_or_else(|| "unknown".to_string());
+    let created_at = raw
+        .created_at
+        .or(raw.submitted_at)
+        .or_else(|| raw.committer.and_then(|c| c.date))
+        .or_else(|| raw.author.and_then(|a| a.date));
+
+    TimelineEvent {
+        actor,
+        created_at,
+        kind,
     }
+}
+
+fn to_review_thread(raw: GhReviewThread) -> ReviewThread {
+    let diff_hunk = raw
+        .comments
+        .nodes
+        .first()
+        .map(|c| c.diff_hunk.clone())
+        .unwrap_or_default();
 
-    let items: Vec<IssueListItem> = serde_json::from_slice(&output.stdout)
-        .context("Failed to parse JSON output from 'gh issue list'")?;
+    let comments = raw
+        .comments
+        .nodes
+        .into_iter()
+        .map(|c| UnifiedComment {
+            author: c.author.map(|a| a.login).unwrap_or_else(|| "ghost".to_string()),
+            body: c.body,
+            created_at: c.created_at,
+            is_answer: false,
+            replies: Vec::new(),
+        })
+        .collect();
 
-    Ok(items.into_iter().map(|item| item.number).collect())
+    ReviewThread {
+        path: raw.path,
+        line: raw.line,
+        start_line: raw.start_line,
+        original_line: raw.original_line,
+        original_start_line: raw.original_start_line,
+        diff_hunk,
+        is_resolved: raw.is_resolved,
+        is_outdated: raw.is_outdated,
+        comments,
+    }
 }
 
-fn fetch_timeline(target: &Target) -> Result<Vec<serde_json::Value>> {
+fn fetch_checks(
+    backend: &dyn Backend,
+    target: &Target,
+    sha: &str,
+    options: &FetchOptions,
+) -> Result<Checks> {
     let repo_arg = format!("{}/{}", target.owner, target.repo);
-    let endpoint = format!("repos/{}/issues/{}/timeline", repo_arg, target.number);
-
-    let output = Command::new("gh")
-        .arg("api")
-        .arg(&endpoint)
-        .arg("--method")
-        .arg("GET")
-        .arg("--paginate")
-        .output()
-        .context("Failed to execute 'gh api' for timeline")?;
-
-    if !output.status.success() {
-        // Timeline might fail or be empty, strictly speaking we could return error
-        // but for now let's just log it or return empty?
-        // User asked for reliability. If it fails, maybe we should warn?
-        // Let's generic error.
-         let stderr = String::from_utf8_lossy(&output.stderr);
-         return Err(anyhow!("'gh api' failed: {}", stderr));
+
+    let endpoint = format!("repos/{}/commits/{}/check-runs?per_page=100", repo_arg, sha);
+    let mut check_runs = Vec::new();
+    for page in backend.rest_pages(&endpoint)? {
+        let page: GhCheckRunsPage =
+            serde_json::from_value(page).context("Failed to parse check runs from GitHub")?;
+        for run in page.check_runs {
+            let failing = is_failing_conclusion(run.conclusion.as_deref());
+            let annotations = if failing && run.output.annotations_count > 0 {
+                fetch_annotations(backend, &repo_arg, run.id)?
+            } else {
+                Vec::new()
+            };
+            let is_actions_job = run.app.as_ref().is_some_and(|a| a.slug == "github-actions");
+            // Logs expire after a retention period, so a missing log only
+            // drops the excerpt rather than failing the whole context. Rate
+            // limits and auth failures would hit every other request too.
+            let log_excerpt = match options.job_log_limit {
+                Some(limit) if failing && is_actions_job => {
+                    match fetch_job_log(backend, &repo_arg, run.id) {
+                        Ok(log) => Some(logs::excerpt(&log, limit)),
+                        Err(err) => match ErrorKind::of(&err) {
+                            ErrorKind::NotFound => None,
+                            ErrorKind::RateLimited | ErrorKind::AuthRequired => return Err(err),
+                            _ => {
+                                eprintln!("Warning: failed to fetch log of job {}: {:#}", run.id, err);
+                                None
+                            }
+                        },
+                    }
+                }
+                _ => None,
+            };
+            check_runs.push(CheckRun {
+                id: run.id,
+                name: run.name,
+                status: run.status,
+                conclusion: run.conclusion,
+                url: run.html_url,
+                app: run.app.map(|a| a.slug),
+                annotations,
+                log_excerpt,
+            });
+        }
     }
 
-    let events: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout)
-        .context("Failed to parse JSON output from 'gh api' timeline")?;
+    let endpoint = format!("repos/{}/commits/{}/status", repo_arg, sha);
+    let combined: GhCombinedStatus = serde_json::from_value(backend.rest(&endpoint)?)
+        .context("Failed to parse commit status from GitHub")?;
+    let statuses = combined
+        .statuses
+        .into_iter()
+        .map(|s| CommitStatus {
+            context: s.context,
+            state: s.state,
+            description: s.description.filter(|d| !d.is_empty()),
+            url: s.target_url.filter(|u| !u.is_empty()),
+        })
+        .collect();
 
-    Ok(events)
+    Ok(Checks {
+        head_sha: sha.to_string(),
+        check_runs,
+        statuses,
+    })
+}
+
+fn fetch_annotations(
+    backend: &dyn Backend,
+    repo_arg: &str,
+    check_run_id: u64,
+) -> Result<Vec<CheckAnnotation>> {
+    let endpoint = format!(
+        "repos/{}/check-runs/{}/annotations?per_page=100",
+        repo_arg, check_run_id
+    );
+    let raw: Vec<GhCheckAnnotation> =
+        serde_json::from_value(flatten_pages(backend.rest_pages(&endpoint)?)?.into())
+            .context("Failed to parse check run annotations from GitHub")?;
+
+    Ok(raw
+        .into_iter()
+        .map(|a| CheckAnnotation {
+            path: a.path,
+            start_line: a.start_line,
+            end_line: a.end_line,
+            level: a.annotation_level,
+            title: a.title.filter(|t| !t.is_empty()),
+            message: a.message,
+        })
+        .collect())
+}
+
+// For GitHub Actions, a check run's id is also its job id.
+fn fetch_job_log(backend: &dyn Backend, repo_arg: &str, job_id: u64) -> Result<String> {
+    let endpoint = format!("repos/{}/actions/jobs/{}/logs", repo_arg, job_id);
+    backend.rest_text(&endpoint, "application/vnd.github+json")
+}
+
+// `endpoint` is a PR or commit, which both serve a unified diff.
+fn fetch_diff(backend: &dyn Backend, endpoint: &str, filter: &DiffFilter) -> Result<Vec<FileDiff>> {
+    let patch = backend.rest_text(endpoint, "application/vnd.github.diff")?;
+
+    Ok(split_patch(&patch)
+        .into_iter()
+        .filter(|file| filter.matches(&file.path))
+        .collect())
 }
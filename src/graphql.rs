@@ -0,0 +1,519 @@
+use crate::backend::Backend;
+use crate::error::GhError;
+use crate::gh::{Target, TargetType};
+use crate::wire::{GhResponse, GhReviewThread};
+use anyhow::{anyhow, Context as _, Result};
+use serde_json::{json, Map, Value};
+
+/// Everything the single-query path returns for one issue or PR.
+pub struct GraphqlItem {
+    pub response: GhResponse,
+    /// Timeline events in the shape of the REST timeline API.
+    pub events: Vec<Value>,
+    pub review_threads: Vec<GhReviewThread>,
+}
+
+// A paginated connection on an issue or PR node.
+struct Connection {
+    name: &'static str,
+    page_size: u32,
+    args: &'static str,
+    fields: &'static str,
+}
+
+const COMMENTS: Connection = Connection {
+    name: "comments",
+    page_size: 100,
+    args: "",
+    fields: "author { login } body createdAt",
+};
+
+// Top-level discussion comments carry their first replies; comments with
+// more are completed by `complete_replies`.
+const DISCUSSION_COMMENTS: Connection = Connection {
+    name: "comments",
+    page_size: 50,
+    args: "",
+    fields: "id author { login } body createdAt isAnswer \
+             replies(first: 50) { pageInfo { hasNextPage endCursor } \
+             nodes { author { login } body createdAt } }",
+};
+
+const REPLIES_QUERY: &str = "query($id: ID!, $endCursor: String) { node(id: $id) { \
+    ... on DiscussionComment { replies(first: 100, after: $endCursor) { \
+    pageInfo { hasNextPage endCursor } nodes { author { login } body createdAt } } } } }";
+
+const LABELS: Connection = Connection {
+    name: "labels",
+    page_size: 100,
+    args: "",
+    fields: "name",
+};
+
+const ASSIGNEES: Connection = Connection {
+    name: "assignees",
+    page_size: 100,
+    args: "",
+    fields: "login",
+};
+
+const REVIEWS: Connection = Connection {
+    name: "reviews",
+    page_size: 100,
+    args: "",
+    fields: "author { login } state submittedAt body",
+};
+
+const COMMITS: Connection = Connection {
+    name: "commits",
+    page_size: 100,
+    args: "",
+    fields: "commit { oid authoredDate messageHeadline messageBody \
+             authors(first: 10) { nodes { name user { login } } } }",
+};
+
+// Threads carry up to 100 comments each, so fetch fewer per page. Threads
+// with more are completed by `complete_thread`.
+const REVIEW_THREADS: Connection = Connection {
+    name: "reviewThreads",
+    page_size: 50,
+    args: "",
+    fields: "id path line startLine originalLine originalStartLine isResolved isOutdated \
+             comments(first: 100) { pageInfo { hasNextPage endCursor } \
+             nodes { author { login } body createdAt diffHunk } }",
+};
+
+const THREAD_COMMENTS_QUERY: &str = "query($id: ID!, $endCursor: String) { node(id: $id) { \
+    ... on PullRequestReviewThread { comments(first: 100, after: $endCursor) { \
+    pageInfo { hasNextPage endCursor } nodes { author { login } body createdAt diffHunk } } } } }";
+
+const ISSUE_TIMELINE: Connection = Connection {
+    name: "timelineItems",
+    page_size: 100,
+    args: "itemTypes: [LABELED_EVENT, UNLABELED_EVENT, ASSIGNED_EVENT, UNASSIGNED_EVENT, \
+           RENAMED_TITLE_EVENT, CLOSED_EVENT, REOPENED_EVENT, REFERENCED_EVENT, \
+           CROSS_REFERENCED_EVENT, MILESTONED_EVENT, DEMILESTONED_EVENT, LOCKED_EVENT, \
+           UNLOCKED_EVENT]",
+    fields: TIMELINE_FIELDS,
+};
+
+const PR_TIMELINE: Connection = Connection {
+    name: "timelineItems",
+    page_size: 100,
+    args: "itemTypes: [LABELED_EVENT, UNLABELED_EVENT, ASSIGNED_EVENT, UNASSIGNED_EVENT, \
+           RENAMED_TITLE_EVENT, CLOSED_EVENT, REOPENED_EVENT, REFERENCED_EVENT, \
+           CROSS_REFERENCED_EVENT, MILESTONED_EVENT, DEMILESTONED_EVENT, LOCKED_EVENT, \
+           UNLOCKED_EVENT, MERGED_EVENT, REVIEW_REQUESTED_EVENT, REVIEW_REQUEST_REMOVED_EVENT, \
+           READY_FOR_REVIEW_EVENT, CONVERT_TO_DRAFT_EVENT, HEAD_REF_FORCE_PUSHED_EVENT]",
+    fields: TIMELINE_FIELDS,
+};
+
+const TIMELINE_FIELDS: &str = "__typename
+    ... on LabeledEvent { actor { login } createdAt label { name } }
+    ... on UnlabeledEvent { actor { login } createdAt label { name } }
+    ... on AssignedEvent { actor { login } createdAt assignee { ... on Actor { login } } }
+    ... on UnassignedEvent { actor { login } createdAt assignee { ... on Actor { login } } }
+    ... on RenamedTitleEvent { actor { login } createdAt previousTitle currentTitle }
+    ... on ClosedEvent { actor { login } createdAt stateReason closer { ... on Commit { oid } } }
+    ... on ReopenedEvent { actor { login } createdAt }
+    ... on ReferencedEvent { actor { login } createdAt commit { oid } }
+    ... on CrossReferencedEvent { actor { login } createdAt source {
+        ... on Issue { number title url repository { nameWithOwner } }
+        ... on PullRequest { number title url repository { nameWithOwner } } } }
+    ... on MilestonedEvent { actor { login } createdAt milestoneTitle }
+    ... on DemilestonedEvent { actor { login } createdAt milestoneTitle }
+    ... on LockedEvent { actor { login } createdAt lockReason }
+    ... on UnlockedEvent { actor { login } createdAt }
+    ... on MergedEvent { actor { login } createdAt commit { oid } }
+    ... on ReviewRequestedEvent { actor { login } createdAt requestedReviewer {
+        ... on User { login } ... on Team { name } } }
+    ... on ReviewRequestRemovedEvent { actor { login } createdAt requestedReviewer {
+        ... on User { login } ... on Team { name } } }
+    ... on ReadyForReviewEvent { actor { login } createdAt }
+    ... on ConvertToDraftEvent { actor { login } createdAt }
+    ... on HeadRefForcePushedEvent { actor { login } createdAt }";
+
+const ISSUE_FIELDS: &str = "title body url number author { login } state stateReason locked \
+                            milestone { title } createdAt updatedAt closedAt";
+
+const PR_FIELDS: &str = "title body url number author { login } state isDraft locked \
+                         milestone { title } createdAt updatedAt closedAt mergedAt \
+                         baseRefName headRefName headRefOid";
+
+const DISCUSSION_FIELDS: &str = "title body url number author { login } closed stateReason \
+                                 locked category { name } createdAt updatedAt closedAt";
+
+fn connections(kind: &TargetType) -> Vec<&'static Connection> {
+    match kind {
+        TargetType::Issue => vec![&COMMENTS, &LABELS, &ASSIGNEES, &ISSUE_TIMELINE],
+        TargetType::Pr => vec![
+            &COMMENTS,
+            &LABELS,
+            &ASSIGNEES,
+            &PR_TIMELINE,
+            &REVIEWS,
+            &COMMITS,
+            &REVIEW_THREADS,
+        ],
+        TargetType::Discussion => vec![&DISCUSSION_COMMENTS, &LABELS],
+        TargetType::Commit { .. } => Vec::new(),
+    }
+}
+
+fn node_field(kind: &TargetType) -> &'static str {
+    match kind {
+        TargetType::Issue => "issue",
+        TargetType::Pr => "pullRequest",
+        TargetType::Discussion => "discussion",
+        TargetType::Commit { .. } => unreachable!("commits are not fetched with GraphQL"),
+    }
+}
+
+fn selection(connection: &Connection, paged: bool) -> String {
+    let mut args = vec![format!("first: {}", connection.page_size)];
+    if paged {
+        args.push("after: $endCursor".to_string());
+    }
+    if !connection.args.is_empty() {
+        args.push(connection.args.to_string());
+    }
+    format!(
+        "{}({}) {{ pageInfo {{ hasNextPage endCursor }} nodes {{ {} }} }}",
+        connection.name,
+        args.join(", "),
+        connection.fields
+    )
+}
+
+fn item_query(kind: &TargetType, body: &str, paged: bool) -> String {
+    let cursor = if paged { ", $endCursor: String" } else { "" };
+    format!(
+        "query($owner: String!, $repo: String!, $number: Int!{}) {{ \
+         repository(owner: $owner, name: $repo) {{ {}(number: $number) {{ {} }} }} }}",
+        cursor,
+        node_field(kind),
+        body
+    )
+}
+
+fn variables(target: &Target) -> Vec<(&'static str, Value)> {
+    vec![
+        ("owner", json!(target.owner)),
+        ("repo", json!(target.repo)),
+        ("number", json!(target.number)),
+    ]
+}
+
+/// Fetches an issue or PR with its comments, labels, assignees, timeline and,
+/// for PRs, reviews, commits and review threads in one GraphQL query.
+/// Discussions come with their category, labels and threaded comments.
+/// Connections with more than one page are completed with follow-up queries.
+pub fn fetch_item(backend: &dyn Backend, target: &Target) -> Result<GraphqlItem> {
+    let scalars = match target.kind {
+        TargetType::Issue => ISSUE_FIELDS,
+        TargetType::Pr => PR_FIELDS,
+        TargetType::Discussion => DISCUSSION_FIELDS,
+        TargetType::Commit { .. } => return Err(anyhow!("Commits are not fetched with GraphQL")),
+    };
+    let number = target.item_number()?;
+    let connections = connections(&target.kind);
+    let body = std::iter::once(scalars.to_string())
+        .chain(connections.iter().map(|c| selection(c, false)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let query = item_query(&target.kind, &body, false);
+
+    let response = backend.graphql(&query, &variables(target))?;
+    let pointer = format!("/data/repository/{}", node_field(&target.kind));
+    let mut node = match response.pointer(&pointer) {
+        Some(Value::Object(node)) => node.clone(),
+        _ => {
+            return Err(GhError::NotFound(format!(
+                "GitHub GraphQL returned no {} #{} in {}/{}",
+                node_field(&target.kind),
+                number,
+                target.owner,
+                target.repo
+            ))
+            .into())
+        }
+    };
+
+    let mut lists: Map<String, Value> = Map::new();
+    for connection in connections {
+        let page = node.remove(connection.name).unwrap_or(Value::Null);
+        let mut nodes = page_nodes(&page);
+        let has_next = page.pointer("/pageInfo/hasNextPage").and_then(Value::as_bool);
+        if has_next == Some(true) {
+            let cursor = page.pointer("/pageInfo/endCursor").cloned();
+            nodes.extend(fetch_connection(backend, target, connection, cursor)?);
+        }
+        lists.insert(connection.name.to_string(), Value::Array(nodes));
+    }
+
+    let take = |lists: &mut Map<String, Value>, name: &str| match lists.remove(name) {
+        Some(Value::Array(items)) => items,
+        _ => Vec::new(),
+    };
+
+    let events = take(&mut lists, "timelineItems")
+        .into_iter()
+        .map(to_rest_event)
+        .collect();
+    let review_threads = parse_threads(backend, take(&mut lists, "reviewThreads"))?;
+
+    let mut comments = take(&mut lists, "comments");
+    if target.kind == TargetType::Discussion {
+        comments = comments
+            .into_iter()
+            .map(|comment| complete_replies(backend, comment))
+            .collect::<Result<_>>()?;
+        // Discussions report `closed` rather than a state.
+        let closed = node.remove("closed").and_then(|v| v.as_bool()).unwrap_or(false);
+        node.insert("state".to_string(), json!(if closed { "CLOSED" } else { "OPEN" }));
+    }
+
+    node.insert("comments".to_string(), Value::Array(comments));
+    node.insert("labels".to_string(), Value::Array(take(&mut lists, "labels")));
+    node.insert("assignees".to_string(), Value::Array(take(&mut lists, "assignees")));
+    node.insert("reviews".to_string(), Value::Array(take(&mut lists, "reviews")));
+    node.insert(
+        "commits".to_string(),
+        Value::Array(take(&mut lists, "commits").into_iter().map(to_gh_commit).collect()),
+    );
+
+    let response: GhResponse = serde_json::from_value(Value::Object(node))
+        .context("Failed to parse issue/PR from GitHub GraphQL")?;
+
+    Ok(GraphqlItem {
+        response,
+        events,
+        review_threads,
+    })
+}
+
+/// When the issue, PR or discussion was last updated; a cheap freshness check.
+pub fn updated_at(backend: &dyn Backend, target: &Target) -> Result<Option<String>> {
+    let query = item_query(&target.kind, "updatedAt", false);
+    let response = backend.graphql(&query, &variables(target))?;
+    let pointer = format!("/data/repository/{}/updatedAt", node_field(&target.kind));
+    Ok(response.pointer(&pointer).and_then(Value::as_str).map(str::to_string))
+}
+
+/// Fetches all review threads of a PR.
+pub fn fetch_review_threads(backend: &dyn Backend, target: &Target) -> Result<Vec<GhReviewThread>> {
+    let nodes = fetch_connection(backend, target, &REVIEW_THREADS, None)?;
+    parse_threads(backend, nodes)
+}
+
+fn parse_threads(backend: &dyn Backend, threads: Vec<Value>) -> Result<Vec<GhReviewThread>> {
+    let threads = threads
+        .into_iter()
+        .map(|thread| complete_thread(backend, thread))
+        .collect::<Result<_>>()?;
+    serde_json::from_value(Value::Array(threads))
+        .context("Failed to parse review threads from GitHub GraphQL")
+}
+
+// Pages through one connection, starting after `cursor` when given.
+fn fetch_connection(
+    backend: &dyn Backend,
+    target: &Target,
+    connection: &Connection,
+    cursor: Option<Value>,
+) -> Result<Vec<Value>> {
+    let query = item_query(&target.kind, &selection(connection, true), true);
+    let mut variables = variables(target);
+    if let Some(cursor) = cursor {
+        variables.push(("endCursor", cursor));
+    }
+
+    let pointer = format!(
+        "/data/repository/{}/{}",
+        node_field(&target.kind),
+        connection.name
+    );
+    let mut nodes = Vec::new();
+    for page in backend.graphql_pages(&query, &variables)? {
+        let page = page.pointer(&pointer).ok_or_else(|| {
+            anyhow!("Unexpected {} response from GitHub GraphQL", connection.name)
+        })?;
+        nodes.extend(page_nodes(page));
+    }
+    Ok(nodes)
+}
+
+// Replaces a discussion comment's `replies` connection with the list of all
+// its replies.
+fn complete_replies(backend: &dyn Backend, mut comment: Value) -> Result<Value> {
+    let replies = nested_nodes(backend, &mut comment, "replies", REPLIES_QUERY)?;
+    if let Value::Object(fields) = &mut comment {
+        fields.insert("replies".to_string(), Value::Array(replies));
+    }
+    Ok(comment)
+}
+
+// Fills in the comments of a review thread beyond the first page, keeping
+// the connection shape the thread is parsed from.
+fn complete_thread(backend: &dyn Backend, mut thread: Value) -> Result<Value> {
+    let comments = nested_nodes(backend, &mut thread, "comments", THREAD_COMMENTS_QUERY)?;
+    if let Value::Object(fields) = &mut thread {
+        fields.insert("comments".to_string(), json!({ "nodes": comments }));
+    }
+    Ok(thread)
+}
+
+// Takes the `field` connection out of a node that has an `id`, returning all
+// of its nodes. Pages beyond the first are fetched with `query`, which pages
+// through `field` of the node by `$id`.
+fn nested_nodes(
+    backend: &dyn Backend,
+    item: &mut Value,
+    field: &str,
+    query: &str,
+) -> Result<Vec<Value>> {
+    let page = item.get_mut(field).map(Value::take).unwrap_or(Value::Null);
+    let mut nodes = page_nodes(&page);
+
+    if page.pointer("/pageInfo/hasNextPage").and_then(Value::as_bool) == Some(true) {
+        let mut variables = vec![("id", item.get("id").cloned().unwrap_or(Value::Null))];
+        if let Some(cursor) = page.pointer("/pageInfo/endCursor") {
+            variables.push(("endCursor", cursor.clone()));
+        }
+        let pointer = format!("/data/node/{}", field);
+        for page in backend.graphql_pages(query, &variables)? {
+            let page = page
+                .pointer(&pointer)
+                .ok_or_else(|| anyhow!("Unexpected {} response from GitHub GraphQL", field))?;
+            nodes.extend(page_nodes(page));
+        }
+    }
+    Ok(nodes)
+}
+
+fn page_nodes(page: &Value) -> Vec<Value> {
+    match page.get("nodes") {
+        Some(Value::Array(nodes)) => nodes.iter().filter(|n| !n.is_null()).cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Flattens a `PullRequestCommit` node into the shape `gh pr view --json commits` uses.
+fn to_gh_commit(node: Value) -> Value {
+    let commit = node.get("commit").cloned().unwrap_or(Value::Null);
+    let authors: Vec<Value> = commit
+        .pointer("/authors/nodes")
+        .and_then(Value::as_array)
+        .map(|authors| {
+            authors
+                .iter()
+                .map(|a| {
+                    json!({
+                        "login": a.pointer("/user/login").cloned().unwrap_or(json!("")),
+                        "name": a.get("name").cloned().unwrap_or(json!("")),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    json!({
+        "oid": commit.get("oid"),
+        "authors": authors,
+        "authoredDate": commit.get("authoredDate"),
+        "messageHeadline": commit.get("messageHeadline"),
+        "messageBody": commit.get("messageBody"),
+    })
+}
+
+/// Converts a GraphQL timeline item into the event shape returned by the
+/// REST timeline API, so both fetch paths produce the same `events`.
+pub fn to_rest_event(node: Value) -> Value {
+    let typename = node.get("__typename").and_then(Value::as_str).unwrap_or("");
+    let field = |path: &str| node.pointer(path).cloned().unwrap_or(Value::Null);
+    let mut event = json!({
+        "event": rest_event_name(typename),
+        "actor": object("login", field("/actor/login")),
+        "created_at": field("/createdAt"),
+    });
+
+    let extra = match typename {
+        "LabeledEvent" | "UnlabeledEvent" => json!({ "label": object("name", field("/label/name")) }),
+        "AssignedEvent" | "UnassignedEvent" => {
+            json!({ "assignee": object("login", field("/assignee/login")) })
+        }
+        "RenamedTitleEvent" => json!({
+            "rename": { "from": field("/previousTitle"), "to": field("/currentTitle") }
+        }),
+        "ClosedEvent" => json!({
+            "commit_id": field("/closer/oid"),
+            "state_reason": lowercase(field("/stateReason")),
+        }),
+        "ReferencedEvent" | "MergedEvent" => json!({ "commit_id": field("/commit/oid") }),
+        "CrossReferencedEvent" => json!({
+            "source": {
+                "type": "issue",
+                "issue": {
+                    "number": field("/source/number"),
+                    "title": field("/source/title"),
+                    "html_url": field("/source/url"),
+                    "repository": { "full_name": field("/source/repository/nameWithOwner") },
+                }
+            }
+        }),
+        "MilestonedEvent" | "DemilestonedEvent" => {
+            json!({ "milestone": object("title", field("/milestoneTitle")) })
+        }
+        "LockedEvent" => json!({ "lock_reason": lowercase(field("/lockReason")) }),
+        "ReviewRequestedEvent" | "ReviewRequestRemovedEvent" => json!({
+            "requested_reviewer": object("login", field("/requestedReviewer/login")),
+            "requested_team": object("name", field("/requestedReviewer/name")),
+        }),
+        _ => json!({}),
+    };
+
+    if let (Value::Object(event), Value::Object(extra)) = (&mut event, extra) {
+        event.extend(extra);
+    }
+    event
+}
+
+// `{ key: value }`, or null when the value is, as for deleted users (ghosts)
+// and teams, so the event still deserializes without them.
+fn object(key: &str, value: Value) -> Value {
+    if value.is_null() {
+        Value::Null
+    } else {
+        json!({ key: value })
+    }
+}
+
+fn lowercase(value: Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(s.to_lowercase()),
+        other => other,
+    }
+}
+
+// `RenamedTitleEvent` -> `renamed`, `ReviewRequestedEvent` -> `review_requested`, ...
+fn rest_event_name(typename: &str) -> String {
+    match typename {
+        "RenamedTitleEvent" => "renamed".to_string(),
+        "CrossReferencedEvent" => "cross-referenced".to_string(),
+        _ => {
+            let base = typename.strip_suffix("Event").unwrap_or(typename);
+            let mut name = String::new();
+            for (i, c) in base.chars().enumerate() {
+                if c.is_uppercase() {
+                    if i > 0 {
+                        name.push('_');
+                    }
+                    name.extend(c.to_lowercase());
+                } else {
+                    name.push(c);
+                }
+            }
+            name
+        }
+    }
+}
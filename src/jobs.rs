@@ -0,0 +1,63 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Runs `work` on each item with up to `jobs` worker threads and hands the
+/// results to `handle` on the calling thread in item order, whatever order
+/// they finish in. That keeps file writes, checkpoints and console output
+/// the same as a sequential run.
+///
+/// Once `handle` returns an error, workers take no further items; the error
+/// is returned after the ones in flight have finished.
+pub fn run_ordered<I, T, W, H>(items: &[I], jobs: usize, work: W, mut handle: H) -> Result<()>
+where
+    I: Sync,
+    T: Send,
+    W: Fn(&I) -> T + Sync,
+    H: FnMut(&I, T) -> Result<()>,
+{
+    if jobs <= 1 || items.len() <= 1 {
+        for item in items {
+            handle(item, work(item))?;
+        }
+        return Ok(());
+    }
+
+    let next = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(items.len()) {
+            let sender = sender.clone();
+            let (next, stop, work) = (&next, &stop, &work);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else { break };
+                    if sender.send((index, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Results that finished ahead of an earlier item wait here.
+        let mut finished = BTreeMap::new();
+        let mut emitted = 0;
+        for (index, result) in receiver {
+            finished.insert(index, result);
+            while let Some(result) = finished.remove(&emitted) {
+                if let Err(err) = handle(&items[emitted], result) {
+                    stop.store(true, Ordering::Relaxed);
+                    return Err(err);
+                }
+                emitted += 1;
+            }
+        }
+        Ok(())
+    })
+}
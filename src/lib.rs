@@ -0,0 +1,74 @@
+//! Fetches GitHub issues, pull requests, discussions and commits with their
+//! conversation, timeline and optional extras (diffs, CI checks, referenced
+//! items), and renders them as Markdown or JSON for use as LLM context.
+//!
+//! The `gh-context` binary is a thin command-line wrapper around this crate.
+//!
+//! ```no_run
+//! use gh_context::{fetch_context, parse_target, render, FetchOptions, Format, GhBackend};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let target = parse_target("https://github.com/cli/cli/issues/123", false, false)?;
+//! let backend = GhBackend::new(&target.host);
+//! let options = FetchOptions::builder().checks(true).build()?;
+//!
+//! let context = fetch_context(&backend, &target, &options)?;
+//! println!("{}", render(&context, Format::Markdown)?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Use [`NativeBackend`] to call the GitHub API directly instead of through
+//! the `gh` CLI, and wrap either in [`retry::RetryBackend`] for the CLI's
+//! retry behavior.
+
+/// The [`Backend`] trait and its `gh` CLI and HTTP implementations.
+pub mod backend;
+/// Typed GitHub failures and exit codes.
+pub mod error;
+/// Retrying rate limits and transient failures.
+pub mod retry;
+/// Running `gh`, and recording and replaying its output.
+pub mod runner;
+/// The context model.
+pub mod types;
+/// Raw response shapes: what [`Backend::view`] returns, and what the REST
+/// and GraphQL responses are parsed into before they become a [`Context`].
+pub mod wire;
+
+// Used by the `gh-context` binary; not a stable API.
+#[doc(hidden)]
+pub mod attachments;
+#[doc(hidden)]
+pub mod batch;
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod checkpoint;
+#[doc(hidden)]
+pub mod gh;
+#[doc(hidden)]
+pub mod jobs;
+#[doc(hidden)]
+pub mod report;
+#[doc(hidden)]
+pub mod sync;
+
+mod diff;
+mod format;
+mod graphql;
+mod logs;
+mod refs;
+
+#[cfg(test)]
+mod __tests__;
+
+pub use backend::{Backend, GhBackend, NativeBackend};
+pub use diff::DiffFilter;
+pub use error::{ErrorKind, GhError};
+pub use format::{render, Format};
+pub use gh::{
+    fetch_context, parse_repo, parse_target, FetchOptions, FetchOptionsBuilder, Target, TargetType,
+};
+pub use refs::FollowRefs;
+pub use types::Context;
@@ -0,0 +1,96 @@
+// Lines around an error marker that are kept for context.
+const CONTEXT_BEFORE: usize = 3;
+const CONTEXT_AFTER: usize = 12;
+
+const ERROR_MARKERS: &[&str] = &[
+    "##[error]",
+    "panicked at",
+    "error[E",
+    "error:",
+    "Error:",
+    "FAILED",
+    "failures:",
+    "Traceback (most recent call last)",
+    "npm ERR!",
+];
+
+/// Builds a short excerpt of a GitHub Actions job log for a prompt.
+///
+/// Lines near error markers (`##[error]`, panics, compiler errors, test
+/// failure summaries) are kept with some surrounding context; when nothing
+/// matches, the tail of the log is used instead. The result never exceeds
+/// `max_bytes`, dropping the oldest lines first.
+pub fn excerpt(log: &str, max_bytes: usize) -> String {
+    let lines: Vec<&str> = log
+        .lines()
+        .map(strip_timestamp)
+        .filter(|line| !line.starts_with("##[group]") && !line.starts_with("##[endgroup]"))
+        .collect();
+
+    let mut keep = vec![false; lines.len()];
+    let mut matched = false;
+    for (i, line) in lines.iter().enumerate() {
+        if ERROR_MARKERS.iter().any(|m| line.contains(m)) {
+            matched = true;
+            let start = i.saturating_sub(CONTEXT_BEFORE);
+            let end = (i + CONTEXT_AFTER + 1).min(lines.len());
+            keep[start..end].iter_mut().for_each(|k| *k = true);
+        }
+    }
+    if !matched {
+        keep.iter_mut().for_each(|k| *k = true);
+    }
+
+    // Gaps between kept windows are marked so the reader knows lines were cut.
+    let mut selected: Vec<&str> = Vec::new();
+    let mut skipping = false;
+    for (line, kept) in lines.iter().zip(&keep) {
+        if *kept {
+            selected.push(line);
+            skipping = false;
+        } else if !skipping {
+            selected.push("...");
+            skipping = true;
+        }
+    }
+
+    tail_bytes(&selected, max_bytes)
+}
+
+// Actions prefixes every line with an RFC 3339 timestamp and a space.
+fn strip_timestamp(line: &str) -> &str {
+    match line.split_once(' ') {
+        Some((stamp, rest))
+            if stamp.len() >= 20
+                && stamp.ends_with('Z')
+                && stamp.as_bytes()[4] == b'-'
+                && stamp.as_bytes()[10] == b'T' =>
+        {
+            rest
+        }
+        _ => line,
+    }
+}
+
+fn tail_bytes(lines: &[&str], max_bytes: usize) -> String {
+    const MARKER: &str = "... [truncated]\n";
+
+    let joined = lines.join("\n");
+    if joined.len() <= max_bytes {
+        return joined;
+    }
+
+    let budget = max_bytes.saturating_sub(MARKER.len());
+    let mut start = joined.len() - budget;
+    while !joined.is_char_boundary(start) {
+        start += 1;
+    }
+    // Start on a whole line where possible.
+    if let Some(offset) = joined[start..].find('\n')
+        && start + offset + 1 < joined.len()
+    {
+        start += offset + 1;
+    }
+
+    format!("{}{}", MARKER, &joined[start..])
+}
@@ -1,131 +1,234 @@
 mod args;
 mod bulk;
-mod format;
-mod gh;
-mod types;
 
 #[cfg(test)]
-mod __tests__;
+#[path = "__tests__/bulk_test.rs"]
+mod bulk_test;
 
-use anyhow::{anyhow, Context, Result};
-use args::{Cli, OutputFormat};
+use anyhow::{Context, Result};
+use args::{BackendKind, Cli, Layout, OutputFormat};
 use bulk::{
     resolve_bulk_out_dir, resolve_pr_range_out_dir, validate_bulk_args, validate_pr_range_args,
+    validate_jobs_args, validate_resume_args, validate_sync_args,
 };
 use clap::Parser;
+use gh_context::batch::Batch;
+use gh_context::checkpoint::Checkpoint;
+use gh_context::error::invalid_args;
+use gh_context::{
+    attachments, cache, error, gh, retry, runner, sync, types, Backend, DiffFilter,
+    Format, GhBackend, NativeBackend,
+};
 use std::fs;
 use std::io::Write;
-use std::process::{Command, Stdio};
-use types::Context as GhContext;
+use std::path::Path;
+use std::process::{Command, ExitCode, Stdio};
+use std::time::Duration;
+use gh_context::Context as GhContext;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    match run(cli) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {:?}", err);
+            ExitCode::from(error::ErrorKind::of(&err).exit_code())
+        }
+    }
+}
 
-    if cli.from.is_some() || cli.to.is_some() {
-        let (from, to) = validate_pr_range_args(&cli)?;
-        let (owner, repo) = gh::parse_repo(&cli.input)?;
-        let out_dir = resolve_pr_range_out_dir(&cli, &repo)?;
-        let file_extension = output_extension(&cli.format);
-        let mut failures: Vec<(u64, String)> = Vec::new();
-        let mut generated_count = 0_u64;
-
-        for number in from..=to {
-            let target = gh::Target {
-                owner: owner.clone(),
-                repo: repo.clone(),
-                number,
-                kind: gh::TargetType::Pr,
-            };
+// What the command line asks for, with its input parsed.
+enum Mode {
+    Range {
+        owner: String,
+        repo: String,
+        from: u64,
+        to: u64,
+    },
+    Bulk {
+        owner: String,
+        repo: String,
+    },
+    Single(gh::Target),
+}
 
-            let context = match gh::fetch_context(&target) {
-                Ok(context) => context,
-                Err(err) => {
-                    failures.push((number, err.to_string()));
-                    continue;
-                }
-            };
-            let formatted_output = format_output(&context, &cli.format)?;
-            let base = format!(
-                "{}-{}-{}",
-                repo, context.metadata.r#type, context.metadata.number
-            );
-            let file_path = out_dir.join(format!("{}.{}", base, file_extension));
-            fs::write(&file_path, &formatted_output).with_context(|| {
-                format!("Failed to write output to file: {:?}", file_path)
-            })?;
-            println!("Generated context in {}", file_path.display());
-            generated_count += 1;
-        }
+fn run(cli: Cli) -> Result<ExitCode> {
+    let options = fetch_options(&cli)?;
+    let host = resolve_host(&cli)?;
+    let mode = parse_mode(&cli, &host)?;
+    let backend = make_backend(&cli, &host)?;
+    let cache = make_cache(&cli)?;
+    let fetch = |target: &gh::Target| fetch_context(cache.as_ref(), backend.as_ref(), target, &options);
+
+    if let Mode::Range { owner, repo, from, to } = &mode {
+        let (from, to) = (*from, *to);
+        let out_dir = resolve_pr_range_out_dir(&cli, repo)?;
 
-        if !failures.is_empty() {
-            eprintln!(
-                "Completed with {} failed PR(s) in range {}..={}",
-                failures.len(),
-                from,
-                to
+        let run = format!("range {}/{} {}..={}", owner, repo, from, to);
+        let mut checkpoint = if cli.resume {
+            Checkpoint::resume(&out_dir, &run)?
+        } else {
+            Checkpoint::start(&out_dir, &run, &(from..=to).collect::<Vec<_>>())?
+        };
+        let remaining = checkpoint.remaining();
+        if remaining.is_empty() {
+            println!("All {} PR(s) in range {}..={} are already done.", checkpoint.total(), from, to);
+            return Ok(ExitCode::SUCCESS);
+        }
+        if cli.resume {
+            println!(
+                "Resuming: {} of {} PR(s) left",
+                remaining.len(),
+                checkpoint.total()
             );
-            for (number, err) in &failures {
-                eprintln!(" - PR #{}: {}", number, err);
-            }
         }
 
-        if generated_count == 0 {
-            return Err(anyhow!(
-                "No PR context files were generated for range {}..={}",
-                from,
-                to
-            ));
-        }
+        let batch = make_batch(&cli, &host, owner, repo, gh::TargetType::Pr, &out_dir);
+        let failures = batch.run(&remaining, |&number| number, fetch, |&number, result| match result {
+            Ok(file_name) => checkpoint.done(number, file_name),
+            Err(failure) => checkpoint.failed(number, &failure.message),
+        })?;
 
-        return Ok(());
+        let scope = format!("in range {}..={}", from, to);
+        return batch.finish("PR", &scope, checkpoint.done_count(), &failures);
     }
 
-    if cli.bulk {
-        validate_bulk_args(&cli)?;
+    if let Mode::Bulk { owner, repo } = &mode {
+        if cli.sync {
+            let out_dir = resolve_bulk_out_dir(&cli, repo)?;
+            let file_extension = output_format(&cli).extension();
+            let batch = make_batch(&cli, &host, owner, repo, gh::TargetType::Issue, &out_dir);
+            let repo_arg = format!("{}/{}", owner, repo);
+            let filter = cli.state.as_str();
+            let mut state = sync::load_state(&out_dir, &repo_arg)?.unwrap_or_else(|| {
+                sync::SyncState {
+                    repo: repo_arg.clone(),
+                    state: String::new(),
+                    last_update: None,
+                    options: String::new(),
+                    issues: Default::default(),
+                }
+            });
+            // Everything that shapes the files. The Debug output is not a
+            // stable format; a change only costs one full regeneration.
+            sync::set_options(&mut state, &format!("{:?} {:?}", output_format(&cli), options));
+            let since = if cli.sync_full {
+                None
+            } else {
+                sync::since(&state, filter, &out_dir)
+            };
+            let listed = sync::list_issues(backend.as_ref(), owner, repo, filter, since)?;
+            let last_update = sync::last_update(&state, &listed);
+            let plan = sync::plan(&state, listed, since.is_none(), filter, &out_dir, file_extension);
+            state.state = filter.to_string();
 
-        let (owner, repo) = gh::parse_repo(&cli.input)?;
-        let repo_arg = format!("{}/{}", owner, repo);
-        let issue_numbers =
-            gh::list_issue_numbers(&repo_arg, cli.state.as_str(), cli.per_page, cli.pages)?;
+            // The state is saved after every file, so an interrupted sync
+            // picks up where it stopped.
+            for (number, synced) in &plan.remove {
+                let file_path = out_dir.join(&synced.file);
+                if file_path.exists() {
+                    fs::remove_file(&file_path).with_context(|| {
+                        format!("Failed to remove file: {:?}", file_path)
+                    })?;
+                }
+                state.issues.remove(number);
+                sync::save_state(&out_dir, &state)?;
+                println!("Removed {} (issue #{} is no longer listed)", file_path.display(), number);
+            }
+
+            let number = |issue: &sync::ListedIssue| issue.number;
+            let failures = batch.run(&plan.fetch, number, fetch, |issue, result| {
+                // A failed issue keeps its old file and state, so the next
+                // sync retries it.
+                let Ok(file_name) = result else {
+                    return Ok(());
+                };
+                // Written in another format last time
+                if let Some(old) = state.issues.get(&issue.number)
+                    && old.file != file_name
+                {
+                    let _ = fs::remove_file(out_dir.join(&old.file));
+                }
+                state.issues.insert(
+                    issue.number,
+                    sync::SyncedIssue {
+                        updated_at: issue.updated_at.clone(),
+                        file: file_name.to_string(),
+                    },
+                );
+                sync::save_state(&out_dir, &state)
+            })?;
 
-        if issue_numbers.is_empty() {
-            println!("No issues found.");
-            return Ok(());
+            // Failed issues are listed again next time.
+            if failures.is_empty() {
+                state.last_update = last_update;
+            }
+            sync::save_state(&out_dir, &state)?;
+            let updated = plan.fetch.len() - failures.len();
+            println!(
+                "Synced {}: {} updated, {} removed, {} unchanged",
+                repo_arg,
+                updated,
+                plan.remove.len(),
+                plan.unchanged
+            );
+            let scope = format!("for {}", repo_arg);
+            return batch.finish("issue", &scope, updated + plan.unchanged, &failures);
         }
 
-        let out_dir = resolve_bulk_out_dir(&cli, &repo)?;
-        let file_extension = output_extension(&cli.format);
+        let limit = u64::from(cli.per_page()) * u64::from(cli.pages());
+        let run = format!("bulk {}/{} state={} limit={}", owner, repo, cli.state.as_str(), limit);
 
-        for number in issue_numbers {
-            let target = gh::Target {
-                owner: owner.clone(),
-                repo: repo.clone(),
-                number,
-                kind: gh::TargetType::Issue,
-            };
+        // A resumed run keeps the issue list it started with.
+        let (out_dir, mut checkpoint) = if cli.resume {
+            let out_dir = resolve_bulk_out_dir(&cli, repo)?;
+            let checkpoint = Checkpoint::resume(&out_dir, &run)?;
+            (out_dir, checkpoint)
+        } else {
+            let repo_arg = format!("{}/{}", owner, repo);
+            let issue_numbers = gh::list_issue_numbers(
+                backend.as_ref(),
+                &repo_arg,
+                cli.state.as_str(),
+                cli.per_page(),
+                cli.pages(),
+            )?;
+
+            if issue_numbers.is_empty() {
+                println!("No issues found.");
+                return Ok(ExitCode::SUCCESS);
+            }
 
-            let context = gh::fetch_context(&target)?;
-            let formatted_output = format_output(&context, &cli.format)?;
+            let out_dir = resolve_bulk_out_dir(&cli, repo)?;
+            let checkpoint = Checkpoint::start(&out_dir, &run, &issue_numbers)?;
+            (out_dir, checkpoint)
+        };
 
-            let base = format!(
-                "{}-{}-{}",
-                repo, context.metadata.r#type, context.metadata.number
+        let remaining = checkpoint.remaining();
+        if cli.resume {
+            println!(
+                "Resuming: {} of {} issue(s) left",
+                remaining.len(),
+                checkpoint.total()
             );
-
-            let file_path = out_dir.join(format!("{}.{}", base, file_extension));
-            fs::write(&file_path, &formatted_output).with_context(|| {
-                format!("Failed to write output to file: {:?}", file_path)
-            })?;
-            println!("Generated context in {}", file_path.display());
         }
 
-        return Ok(());
+        let batch = make_batch(&cli, &host, owner, repo, gh::TargetType::Issue, &out_dir);
+        let failures = batch.run(&remaining, |&number| number, fetch, |&number, result| match result {
+            Ok(file_name) => checkpoint.done(number, file_name),
+            Err(failure) => checkpoint.failed(number, &failure.message),
+        })?;
+
+        let scope = format!("for {}/{}", owner, repo);
+        return batch.finish("issue", &scope, checkpoint.done_count(), &failures);
     }
 
-    let target = gh::parse_target(&cli.input, cli.issue, cli.pr)?;
-    let context = gh::fetch_context(&target)?;
+    let Mode::Single(target) = mode else {
+        unreachable!("range and bulk runs return above");
+    };
+    let context = fetch_context(cache.as_ref(), backend.as_ref(), &target, &options)?;
 
-    let formatted_output = format_output(&context, &cli.format)?;
+    let mut formatted_output = format_output(&context, &cli)?;
 
     if let Some(path) = cli.out {
         fs::write(&path, &formatted_output)
@@ -142,13 +245,38 @@ fn main() -> Result<()> {
                     .unwrap_or(&context.metadata.repo);
                 let folder_name = format!(
                     "{}-{}-{}",
-                    repo_slug, context.metadata.r#type, context.metadata.number
+                    repo_slug,
+                    context.metadata.r#type,
+                    item_id(&context.metadata)
                 );
                 let folder_path = std::path::Path::new(&folder_name);
                 if !folder_path.exists() {
                     fs::create_dir(folder_path).context("Failed to create directory")?;
                 }
 
+                if cli.attachments {
+                    let limits = attachments::Limits {
+                        max_file_bytes: cli.attachment_limit,
+                        max_total_bytes: cli.attachments_total_limit,
+                    };
+                    let urls = attachments::find_urls(&context);
+                    let entries = attachments::download_all(&urls, folder_path, &limits)?;
+                    let saved = entries.iter().filter(|e| e.path.is_some()).count();
+                    if saved < entries.len() {
+                        eprintln!(
+                            "Skipped {} of {} attachment(s); see {}/manifest.json",
+                            entries.len() - saved,
+                            entries.len(),
+                            folder_path.join(attachments::DIR).display()
+                        );
+                    }
+                    formatted_output = attachments::rewrite_links(
+                        &formatted_output,
+                        &context.metadata.host,
+                        &entries,
+                    );
+                }
+
                 let file_path = folder_path.join(format!("{}.md", folder_name));
                 fs::write(&file_path, &formatted_output).with_context(|| {
                     format!("Failed to write output to file: {:?}", file_path)
@@ -176,19 +304,208 @@ fn main() -> Result<()> {
         }
     }
 
+    Ok(ExitCode::SUCCESS)
+}
+
+// Issue/PR/discussion number, or short SHA for commits.
+fn item_id(metadata: &types::Metadata) -> String {
+    match (metadata.number, &metadata.sha) {
+        (Some(number), _) => number.to_string(),
+        (None, Some(sha)) => sha.get(..7).unwrap_or(sha).to_string(),
+        (None, None) => "unknown".to_string(),
+    }
+}
+
+// Checks every argument and parses the input before a backend or cache is
+// set up, so a bad argument is reported as such rather than as, say, a
+// missing token.
+fn parse_mode(cli: &Cli, host: &str) -> Result<Mode> {
+    validate_attachment_args(cli)?;
+    validate_sync_args(cli)?;
+    validate_resume_args(cli)?;
+    validate_jobs_args(cli)?;
+    validate_backend_args(cli)?;
+    validate_cache_args(cli)?;
+
+    if cli.from.is_some() || cli.to.is_some() {
+        let (from, to) = validate_pr_range_args(cli)?;
+        let (owner, repo) = gh::parse_repo(&cli.input)?;
+        return Ok(Mode::Range {
+            owner,
+            repo,
+            from,
+            to,
+        });
+    }
+    if cli.bulk {
+        validate_bulk_args(cli)?;
+        let (owner, repo) = gh::parse_repo(&cli.input)?;
+        return Ok(Mode::Bulk { owner, repo });
+    }
+    let mut target = gh::parse_target(&cli.input, cli.issue, cli.pr)?;
+    target.host = host.to_string();
+    Ok(Mode::Single(target))
+}
+
+fn validate_backend_args(cli: &Cli) -> Result<()> {
+    match cli.backend {
+        BackendKind::Gh if cli.pace => Err(invalid_args("--pace requires --backend native")),
+        BackendKind::Native if cli.record.is_some() || cli.replay.is_some() => {
+            Err(invalid_args("--record and --replay require --backend gh"))
+        }
+        _ if cli.record.is_some() && cli.replay.is_some() => {
+            Err(invalid_args("--record cannot be combined with --replay"))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn validate_cache_args(cli: &Cli) -> Result<()> {
+    if cli.no_cache && cli.refresh {
+        return Err(invalid_args("--refresh cannot be combined with --no-cache"));
+    }
     Ok(())
 }
 
-fn format_output(context: &GhContext, format: &OutputFormat) -> Result<String> {
-    match format {
-        OutputFormat::Json => format::to_json(context),
-        OutputFormat::Md => Ok(format::to_markdown(context)),
+fn make_backend(cli: &Cli, host: &str) -> Result<Box<dyn Backend>> {
+    let backend: Box<dyn Backend> = match cli.backend {
+        BackendKind::Gh => match make_runner(cli)? {
+            Some(runner) => Box::new(GhBackend::with_runner(host, runner)),
+            None => Box::new(GhBackend::new(host)),
+        },
+        BackendKind::Native => Box::new(NativeBackend::from_env(host)?.with_pacing(cli.pace)),
+    };
+    let policy = retry::RetryPolicy {
+        retries: cli.retries,
+        max_wait: Duration::from_secs(cli.max_retry_wait),
+        ..Default::default()
+    };
+    Ok(Box::new(retry::RetryBackend::new(backend, policy)))
+}
+
+fn make_runner(cli: &Cli) -> Result<Option<Box<dyn runner::CommandRunner>>> {
+    Ok(match (&cli.record, &cli.replay) {
+        (Some(path), _) => Some(Box::new(runner::RecordingRunner::new(
+            Box::new(runner::SystemRunner),
+            path,
+        )?)),
+        (None, Some(path)) => Some(Box::new(runner::ReplayRunner::load(path)?)),
+        (None, None) => None,
+    })
+}
+
+// Recording or replaying bypasses the cache, so a cassette holds every
+// response the run needs.
+fn make_cache(cli: &Cli) -> Result<Option<cache::Cache>> {
+    if cli.record.is_some() || cli.replay.is_some() {
+        return Ok(None);
+    }
+    if cli.no_cache {
+        return Ok(None);
+    }
+    let dir = cli.cache_dir.clone().or_else(cache::Cache::default_dir);
+    Ok(dir.map(|dir| cache::Cache::new(dir, cli.refresh)))
+}
+
+fn make_batch<'a>(
+    cli: &Cli,
+    host: &'a str,
+    owner: &'a str,
+    repo: &'a str,
+    kind: gh::TargetType,
+    out_dir: &'a Path,
+) -> Batch<'a> {
+    Batch {
+        host,
+        owner,
+        repo,
+        kind,
+        out_dir,
+        format: output_format(cli),
+        jobs: cli.jobs,
+    }
+}
+
+fn fetch_context(
+    cache: Option<&cache::Cache>,
+    backend: &dyn Backend,
+    target: &gh::Target,
+    options: &gh::FetchOptions,
+) -> Result<GhContext> {
+    match cache {
+        Some(cache) => cache.fetch_context(backend, target, options),
+        None => gh::fetch_context(backend, target, options),
+    }
+}
+
+// A URL input names its own host; --hostname applies to shorthand input.
+fn resolve_host(cli: &Cli) -> Result<String> {
+    match (gh::parse_host(&cli.input), &cli.hostname) {
+        (Some(url_host), Some(hostname)) if !url_host.eq_ignore_ascii_case(hostname) => Err(
+            invalid_args(format!("--hostname {} does not match the URL host {}", hostname, url_host)),
+        ),
+        (Some(url_host), _) => Ok(url_host),
+        (None, Some(hostname)) => Ok(hostname.to_ascii_lowercase()),
+        (None, None) => Ok(types::DEFAULT_HOST.to_string()),
     }
 }
 
-fn output_extension(format: &OutputFormat) -> &'static str {
-    match format {
-        OutputFormat::Json => "json",
-        OutputFormat::Md => "md",
+fn fetch_options(cli: &Cli) -> Result<gh::FetchOptions> {
+    if !cli.diff && (!cli.diff_include.is_empty() || !cli.diff_exclude.is_empty()) {
+        return Err(invalid_args("--diff-include/--diff-exclude require --diff"));
+    }
+
+    let diff = if cli.diff {
+        Some(DiffFilter::new(&cli.diff_include, &cli.diff_exclude)?)
+    } else {
+        None
+    };
+
+    let mut builder = gh::FetchOptions::builder()
+        .checks(cli.checks)
+        .graphql(cli.graphql);
+    if let Some(diff) = diff {
+        builder = builder.diff(diff);
+    }
+    if cli.job_logs {
+        builder = builder.job_logs(cli.job_log_limit);
     }
+    if let Some(depth) = cli.follow_refs {
+        builder = builder.follow_refs(depth, cli.follow_refs_limit);
+    }
+    builder.build()
+}
+
+// Attachments are saved next to the Markdown file of a single item, so they
+// need the folder that only that mode creates.
+fn validate_attachment_args(cli: &Cli) -> Result<()> {
+    if !cli.attachments {
+        return Ok(());
+    }
+    if cli.bulk || cli.from.is_some() || cli.to.is_some() {
+        return Err(invalid_args("--attachments is not supported in bulk or range mode"));
+    }
+    if cli.out.is_some() || !matches!(cli.format, OutputFormat::Md) {
+        return Err(invalid_args(
+            "--attachments requires Markdown output to the default folder (no --out)"
+        ));
+    }
+    if cli.attachment_limit == 0 || cli.attachments_total_limit == 0 {
+        return Err(invalid_args(
+            "--attachment-limit and --attachments-total-limit must be at least 1"
+        ));
+    }
+    Ok(())
+}
+
+fn output_format(cli: &Cli) -> Format {
+    match (&cli.format, &cli.layout) {
+        (OutputFormat::Json, _) => Format::Json,
+        (OutputFormat::Md, Layout::Sections) => Format::Markdown,
+        (OutputFormat::Md, Layout::Chronological) => Format::MarkdownChronological,
+    }
+}
+
+fn format_output(context: &GhContext, cli: &Cli) -> Result<String> {
+    gh_context::render(context, output_format(cli))
 }
@@ -0,0 +1,221 @@
+use crate::backend::Backend;
+use crate::error::ErrorKind;
+use crate::gh::{self, FetchOptions, Target, TargetType};
+use crate::types::{Context, EventKind, Reference};
+use anyhow::Result;
+use std::collections::{HashSet, VecDeque};
+
+/// Limits for following references to other issues and PRs.
+#[derive(Debug, Clone)]
+pub struct FollowRefs {
+    /// How many hops away from the requested item to follow; 1 fetches only
+    /// the items it references directly.
+    pub depth: u32,
+    /// Upper bound on referenced items fetched in total, across all depths.
+    pub max_items: usize,
+}
+
+/// An issue or PR mentioned by another item. `kind` is `None` for shorthand
+/// like `#12`, which GitHub uses for both.
+#[derive(Debug, PartialEq)]
+pub struct ItemRef {
+    pub owner: String,
+    pub repo: String,
+    pub number: u64,
+    pub kind: Option<TargetType>,
+}
+
+/// Finds `#123`, `owner/repo#123` and issue/PR URLs on `host` in `text`.
+/// Fenced code blocks are skipped, since they are mostly logs and code.
+pub fn find_references(text: &str, host: &str, owner: &str, repo: &str) -> Vec<ItemRef> {
+    let mut refs = Vec::new();
+    let mut fence: Option<usize> = None;
+
+    for line in text.lines() {
+        let ticks = line.trim_start().chars().take_while(|&c| c == '`').count();
+        match fence {
+            Some(open) if ticks >= open => fence = None,
+            Some(_) => {}
+            None if ticks >= 3 => fence = Some(ticks),
+            None => find_in_line(line, host, owner, repo, &mut refs),
+        }
+    }
+
+    refs
+}
+
+fn find_in_line(line: &str, host: &str, owner: &str, repo: &str, refs: &mut Vec<ItemRef>) {
+    for word in line.split(|c: char| c.is_whitespace() || "()[]<>\"'`,;".contains(c)) {
+        if word.starts_with("http://") || word.starts_with("https://") {
+            // Commit links are not followed; only issues, PRs and discussions.
+            if let Ok(target) = gh::parse_target(word, false, false)
+                && target.host == host
+                && let Some(number) = target.number
+            {
+                refs.push(ItemRef {
+                    owner: target.owner,
+                    repo: target.repo,
+                    number,
+                    kind: Some(target.kind),
+                });
+            }
+            continue;
+        }
+
+        let word = word.trim_end_matches(['.', ':', '!', '?']);
+        let Some((prefix, number)) = word.split_once('#') else {
+            continue;
+        };
+        let Ok(number) = number.parse::<u64>() else {
+            continue;
+        };
+        let (ref_owner, ref_repo) = match prefix.split_once('/') {
+            None if prefix.is_empty() => (owner, repo),
+            Some((o, r)) if is_name(o) && is_name(r) => (o, r),
+            _ => continue,
+        };
+        refs.push(ItemRef {
+            owner: ref_owner.to_string(),
+            repo: ref_repo.to_string(),
+            number,
+            kind: None,
+        });
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty()
+        && s
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+// References in the body, then comments, then cross-reference events.
+fn references_in(context: &Context, target: &Target) -> Vec<ItemRef> {
+    let find = |text: &str| find_references(text, &target.host, &target.owner, &target.repo);
+
+    let mut refs = find(&context.body);
+    for comment in &context.comments {
+        refs.extend(find(&comment.body));
+    }
+    for event in &context.events {
+        if let EventKind::CrossReferenced { source } = &event.kind {
+            refs.extend(find(&source.url));
+        }
+    }
+    refs
+}
+
+/// Fetches the issues and PRs that `context` references, breadth first, and
+/// appends them to `context.references`. `fetch` fetches each referenced
+/// item, e.g. through the cache.
+///
+/// Each item is fetched at most once, so reference cycles end naturally.
+/// Referenced items get the basic context only (no diff, checks or logs).
+/// Ones that no longer exist are skipped, and ones failing otherwise are
+/// skipped with a warning; both still count toward the cap. Rate limits and
+/// auth failures are returned, as they would fail every later item too.
+pub fn expand(
+    backend: &dyn Backend,
+    target: &Target,
+    context: &mut Context,
+    follow: &FollowRefs,
+    options: &FetchOptions,
+    fetch: &dyn Fn(&Target, &FetchOptions) -> Result<Context>,
+) -> Result<()> {
+    let sub_options = FetchOptions {
+        graphql: options.graphql,
+        ..FetchOptions::default()
+    };
+
+    let mut seen: HashSet<_> = target
+        .number
+        .map(|number| key(&target.owner, &target.repo, number))
+        .into_iter()
+        .collect();
+    let mut queue = VecDeque::new();
+    for item in references_in(context, target) {
+        queue.push_back((label(target), item, 1));
+    }
+
+    let mut fetched = 0;
+    while let Some((from, item, depth)) = queue.pop_front() {
+        if fetched >= follow.max_items {
+            break;
+        }
+        if !seen.insert(key(&item.owner, &item.repo, item.number)) {
+            continue;
+        }
+        fetched += 1;
+
+        let name = format!("{}/{}#{}", item.owner, item.repo, item.number);
+        let found = resolve(backend, &target.host, item).and_then(|sub_target| {
+            let sub_context = fetch(&sub_target, &sub_options)?;
+            Ok((sub_target, sub_context))
+        });
+        let (sub_target, sub_context) = match found {
+            Ok(found) => found,
+            Err(err) => match ErrorKind::of(&err) {
+                ErrorKind::NotFound => continue,
+                ErrorKind::RateLimited | ErrorKind::AuthRequired => {
+                    return Err(err.context(format!("Failed to follow reference to {}", name)));
+                }
+                _ => {
+                    eprintln!("Warning: skipping reference to {}: {:#}", name, err);
+                    continue;
+                }
+            },
+        };
+
+        if depth < follow.depth {
+            for next in references_in(&sub_context, &sub_target) {
+                queue.push_back((label(&sub_target), next, depth + 1));
+            }
+        }
+        context.references.push(Reference {
+            referenced_from: from,
+            depth,
+            context: sub_context,
+        });
+    }
+    Ok(())
+}
+
+// Shorthand references may point at an issue or a PR; the issues endpoint
+// serves both and marks PRs with a `pull_request` key.
+fn resolve(backend: &dyn Backend, host: &str, item: ItemRef) -> Result<Target> {
+    let kind = match item.kind {
+        Some(kind) => kind,
+        None => {
+            let endpoint = format!("repos/{}/{}/issues/{}", item.owner, item.repo, item.number);
+            if backend.rest(&endpoint)?.get("pull_request").is_some() {
+                TargetType::Pr
+            } else {
+                TargetType::Issue
+            }
+        }
+    };
+
+    Ok(Target {
+        host: host.to_string(),
+        owner: item.owner,
+        repo: item.repo,
+        number: Some(item.number),
+        kind,
+    })
+}
+
+// Owner and repo names are case-insensitive on GitHub.
+fn key(owner: &str, repo: &str, number: u64) -> (String, String, u64) {
+    (owner.to_ascii_lowercase(), repo.to_ascii_lowercase(), number)
+}
+
+fn label(target: &Target) -> String {
+    match (&target.kind, target.number) {
+        (TargetType::Commit { sha }, _) => {
+            format!("{}/{}@{}", target.owner, target.repo, sha.get(..7).unwrap_or(sha))
+        }
+        (_, Some(number)) => format!("{}/{}#{}", target.owner, target.repo, number),
+        (_, None) => format!("{}/{}", target.owner, target.repo),
+    }
+}
@@ -0,0 +1,80 @@
+use crate::error::ErrorKind;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Written into the output directory of bulk and range runs with failures.
+pub const REPORT_FILE: &str = "failures.json";
+
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    pub number: u64,
+    pub class: ErrorKind,
+    pub message: String,
+}
+
+impl Failure {
+    pub fn new(number: u64, err: &anyhow::Error) -> Self {
+        Failure {
+            number,
+            class: ErrorKind::of(err),
+            message: format!("{:#}", err),
+        }
+    }
+}
+
+/// How a bulk or range run went.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    /// Every item was generated.
+    Complete,
+    /// Some items failed.
+    Partial,
+    /// No item was generated.
+    Failed,
+}
+
+impl Outcome {
+    pub fn of(succeeded: usize, failed: usize) -> Self {
+        match (succeeded, failed) {
+            (_, 0) => Outcome::Complete,
+            (0, _) => Outcome::Failed,
+            _ => Outcome::Partial,
+        }
+    }
+
+    /// 0 when complete and 3 when partial. A failed run exits like a single
+    /// fetch would when all items failed for the same cause, e.g. 7 when
+    /// every item was rate limited, and with 1 otherwise.
+    pub fn exit_code(&self, failures: &[Failure]) -> ExitCode {
+        match self {
+            Outcome::Complete => ExitCode::SUCCESS,
+            Outcome::Partial => ExitCode::from(3),
+            Outcome::Failed => match failures.first() {
+                Some(first) if failures.iter().all(|f| f.class == first.class) => {
+                    ExitCode::from(first.class.exit_code())
+                }
+                _ => ExitCode::FAILURE,
+            },
+        }
+    }
+}
+
+/// Writes `failures.json` into `dir`, or removes a stale one when there
+/// were no failures. Returns the report's path when one was written.
+pub fn write(dir: &Path, failures: &[Failure]) -> Result<Option<PathBuf>> {
+    let path = dir.join(REPORT_FILE);
+    if failures.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove stale failure report: {:?}", path))?;
+        }
+        return Ok(None);
+    }
+
+    let json = serde_json::to_string_pretty(failures).context("Failed to serialize failure report")?;
+    fs::write(&path, json).with_context(|| format!("Failed to write failure report: {:?}", path))?;
+    Ok(Some(path))
+}
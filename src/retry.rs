@@ -0,0 +1,140 @@
+use crate::backend::{Backend, Conditional};
+use crate::error::GhError;
+use crate::gh::Target;
+use crate::wire::GhResponse;
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::Duration;
+
+/// How often and how long to wait on rate limits and transient failures.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; 0 fails on the first error.
+    pub retries: u32,
+    /// Backoff before the first retry of a network failure, doubled for
+    /// each further one.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Longest single wait. A rate limit that resets later than this fails
+    /// right away rather than stalling the run.
+    pub max_wait: Duration,
+    /// Wait after a secondary rate limit that came without `Retry-After`;
+    /// GitHub asks for at least a minute.
+    pub secondary_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            retries: 3,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_wait: Duration::from_secs(300),
+            secondary_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+/// How long to wait before retry number `attempt` (from 1) after `err`, or
+/// `None` when it should not be retried. Rate limits wait as long as GitHub
+/// asked; network failures and gateway errors back off exponentially with
+/// jitter. Anything else (not found, bad credentials, ...) won't pass by
+/// retrying.
+pub fn delay(err: &anyhow::Error, attempt: u32, policy: &RetryPolicy) -> Option<Duration> {
+    if attempt > policy.retries {
+        return None;
+    }
+    let wait = match GhError::find(err)? {
+        GhError::RateLimited {
+            retry_after: Some(retry_after),
+            ..
+        } => *retry_after,
+        GhError::RateLimited { retry_after: None, .. } => {
+            policy.secondary_delay.max(backoff(attempt, policy))
+        }
+        GhError::Network(_) => jitter(backoff(attempt, policy)),
+        _ => return None,
+    };
+    (wait <= policy.max_wait).then_some(wait)
+}
+
+fn backoff(attempt: u32, policy: &RetryPolicy) -> Duration {
+    policy
+        .base_delay
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(policy.max_delay)
+}
+
+// Somewhere between half and all of `delay`, so parallel clients that failed
+// together don't retry together.
+fn jitter(delay: Duration) -> Duration {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(delay.as_nanos());
+    let fraction = (hasher.finish() % 1000) as f64 / 1000.0;
+    delay / 2 + delay.mul_f64(fraction / 2.0)
+}
+
+/// Wraps a backend, retrying each call according to a [`RetryPolicy`].
+pub struct RetryBackend {
+    inner: Box<dyn Backend>,
+    policy: RetryPolicy,
+}
+
+impl RetryBackend {
+    pub fn new(inner: Box<dyn Backend>, policy: RetryPolicy) -> Self {
+        RetryBackend { inner, policy }
+    }
+
+    fn call<T>(&self, mut request: impl FnMut() -> Result<T>) -> Result<T> {
+        let mut attempt = 0;
+        loop {
+            let err = match request() {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            attempt += 1;
+            let Some(wait) = delay(&err, attempt, &self.policy) else {
+                return Err(err);
+            };
+            eprintln!(
+                "Warning: {}; retrying in {:.1}s ({}/{})",
+                err,
+                wait.as_secs_f64(),
+                attempt,
+                self.policy.retries
+            );
+            thread::sleep(wait);
+        }
+    }
+}
+
+// The default `graphql_pages`, `rest` and `fetch_timeline` go through the
+// retried methods below.
+impl Backend for RetryBackend {
+    fn view(&self, target: &Target) -> Result<GhResponse> {
+        self.call(|| self.inner.view(target))
+    }
+
+    fn list_issue_numbers(&self, repo: &str, state: &str, limit: u64) -> Result<Vec<u64>> {
+        self.call(|| self.inner.list_issue_numbers(repo, state, limit))
+    }
+
+    fn rest_pages(&self, endpoint: &str) -> Result<Vec<Value>> {
+        self.call(|| self.inner.rest_pages(endpoint))
+    }
+
+    fn rest_text(&self, endpoint: &str, accept: &str) -> Result<String> {
+        self.call(|| self.inner.rest_text(endpoint, accept))
+    }
+
+    fn graphql(&self, query: &str, variables: &[(&str, Value)]) -> Result<Value> {
+        self.call(|| self.inner.graphql(query, variables))
+    }
+
+    fn rest_if_changed(&self, endpoint: &str, etag: Option<&str>) -> Result<Conditional> {
+        self.call(|| self.inner.rest_if_changed(endpoint, etag))
+    }
+}
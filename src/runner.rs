@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+/// What a finished command printed and how it exited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommandOutput {
+    /// Exit code, or `None` when the command was killed by a signal.
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+}
+
+/// Runs external commands for [`GhBackend`](crate::backend::GhBackend), so
+/// tests and bug reports can stand in for a real `gh`.
+pub trait CommandRunner: Send + Sync {
+    /// Runs `program` with `args`. Only failing to start the command is an
+    /// error; a non-zero exit is reported in the output.
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// Runs commands for real.
+#[derive(Debug, Default)]
+pub struct SystemRunner;
+
+// Output is kept as text, which is all `gh` prints for the calls made here.
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// One recorded command and its output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub program: String,
+    pub args: Vec<String>,
+    #[serde(flatten)]
+    pub output: CommandOutput,
+}
+
+/// Commands and outputs saved by `--record` and served back by `--replay`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cassette: {:?}", path))?;
+        serde_json::from_str(&json).with_context(|| format!("Failed to parse cassette: {:?}", path))
+    }
+
+    // Written whole and renamed, so an interrupted run keeps the commands
+    // recorded before it.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create directory: {:?}", dir))?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize cassette")?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json).with_context(|| format!("Failed to write cassette: {:?}", tmp))?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to write cassette: {:?}", path))
+    }
+}
+
+/// Runs commands through another runner and appends each one, failures
+/// included, to a cassette file.
+pub struct RecordingRunner {
+    inner: Box<dyn CommandRunner>,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl RecordingRunner {
+    /// Starts a new cassette at `path`, replacing any earlier one.
+    pub fn new(inner: Box<dyn CommandRunner>, path: &Path) -> Result<Self> {
+        let cassette = Cassette::default();
+        cassette.save(path)?;
+        Ok(RecordingRunner {
+            inner,
+            path: path.to_path_buf(),
+            cassette: Mutex::new(cassette),
+        })
+    }
+}
+
+impl CommandRunner for RecordingRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = self.inner.run(program, args)?;
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette.interactions.push(Interaction {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            output: output.clone(),
+        });
+        cassette.save(&self.path).map_err(|err| io::Error::other(format!("{:#}", err)))?;
+        Ok(output)
+    }
+}
+
+// Program and arguments.
+type CommandLine = (String, Vec<String>);
+
+/// Serves the outputs of a cassette instead of running commands. Commands
+/// recorded more than once get their outputs in recording order, the last
+/// one being served from then on.
+pub struct ReplayRunner {
+    path: PathBuf,
+    outputs: Mutex<HashMap<CommandLine, Vec<CommandOutput>>>,
+}
+
+impl ReplayRunner {
+    pub fn new(cassette: Cassette, path: &Path) -> Self {
+        let mut outputs: HashMap<_, Vec<_>> = HashMap::new();
+        for interaction in cassette.interactions {
+            outputs
+                .entry((interaction.program, interaction.args))
+                .or_default()
+                .push(interaction.output);
+        }
+        ReplayRunner {
+            path: path.to_path_buf(),
+            outputs: Mutex::new(outputs),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(ReplayRunner::new(Cassette::load(path)?, path))
+    }
+}
+
+impl CommandRunner for ReplayRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let key: CommandLine = (
+            program.to_string(),
+            args.iter().map(|arg| arg.to_string()).collect(),
+        );
+        let mut outputs = self.outputs.lock().unwrap();
+        match outputs.get_mut(&key) {
+            Some(queue) if queue.len() > 1 => Ok(queue.remove(0)),
+            Some(queue) => Ok(queue[0].clone()),
+            None => Err(io::Error::other(format!(
+                "{:?} has no recorded output for '{} {}'",
+                self.path,
+                program,
+                args.join(" ")
+            ))),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::backend::{flatten_pages, Backend};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Written into the bulk output directory by `--sync`.
+pub const STATE_FILE: &str = ".gh-context-sync.json";
+
+/// What the last sync wrote, so the next one can skip unchanged issues and
+/// remove files for issues that are gone.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SyncState {
+    pub repo: String,
+    /// The `--state` filter of the last sync.
+    pub state: String,
+    /// Update time of the newest issue listed by the last sync without
+    /// failures; the next one only lists issues updated since.
+    #[serde(default)]
+    pub last_update: Option<String>,
+    /// The fetch and output options the files were written with.
+    #[serde(default)]
+    pub options: String,
+    pub issues: BTreeMap<u64, SyncedIssue>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncedIssue {
+    pub updated_at: String,
+    /// File name inside the output directory.
+    pub file: String,
+}
+
+/// An issue as listed by the REST issues endpoint.
+#[derive(Debug, Deserialize)]
+pub struct ListedIssue {
+    pub number: u64,
+    pub updated_at: String,
+    pub state: String,
+    #[serde(default)]
+    pull_request: Option<Value>,
+}
+
+/// What a sync has to do to bring the output directory up to date.
+#[derive(Debug, Default)]
+pub struct Plan {
+    /// New or updated issues, or ones whose file is missing.
+    pub fetch: Vec<ListedIssue>,
+    /// Issues no longer listed: closed (when syncing open issues only),
+    /// transferred to another repo, or deleted.
+    pub remove: Vec<(u64, SyncedIssue)>,
+    pub unchanged: usize,
+}
+
+/// Lists the issues (never PRs) of `owner/repo` with their update time:
+/// every issue in `state`, or with `since`, the issues in any state updated
+/// since then. Unlike `gh::list_issue_numbers` there is no limit, since the
+/// sync state has to cover the whole repo.
+pub fn list_issues(
+    backend: &dyn Backend,
+    owner: &str,
+    repo: &str,
+    state: &str,
+    since: Option<&str>,
+) -> Result<Vec<ListedIssue>> {
+    let endpoint = match since {
+        Some(since) => format!(
+            "repos/{}/{}/issues?state=all&since={}&sort=updated&direction=asc&per_page=100",
+            owner, repo, since
+        ),
+        None => format!(
+            "repos/{}/{}/issues?state={}&sort=created&direction=asc&per_page=100",
+            owner, repo, state
+        ),
+    };
+    let items: Vec<ListedIssue> = serde_json::from_value(flatten_pages(backend.rest_pages(&endpoint)?)?.into())
+        .context("Failed to parse issue list from GitHub")?;
+    Ok(items
+        .into_iter()
+        .filter(|item| item.pull_request.is_none())
+        .collect())
+}
+
+/// Loads the state of an earlier sync of `repo` into `dir`, if any. A sync
+/// with another `--state` reuses it too, so files for issues the new filter
+/// leaves out are removed.
+pub fn load_state(dir: &Path, repo: &str) -> Result<Option<SyncState>> {
+    let path = dir.join(STATE_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read sync state: {:?}", path))?;
+    let saved: SyncState = serde_json::from_str(&json)
+        .with_context(|| format!("Failed to parse sync state: {:?}", path))?;
+
+    if !saved.repo.eq_ignore_ascii_case(repo) {
+        return Err(anyhow!(
+            "{:?} belongs to a sync of {}, not {}; use another --out directory",
+            path,
+            saved.repo,
+            repo
+        ));
+    }
+    Ok(Some(saved))
+}
+
+pub fn save_state(dir: &Path, state: &SyncState) -> Result<()> {
+    let path = dir.join(STATE_FILE);
+    let json = serde_json::to_string_pretty(state).context("Failed to serialize sync state")?;
+    let tmp = dir.join(format!("{}.tmp", STATE_FILE));
+    fs::write(&tmp, json).with_context(|| format!("Failed to write sync state: {:?}", tmp))?;
+    fs::rename(&tmp, &path).with_context(|| format!("Failed to write sync state: {:?}", path))
+}
+
+/// Records the options of this sync. When they differ from the last one,
+/// every issue is marked as outdated first, so all files are written again
+/// even if this sync is interrupted or some issues fail.
+pub fn set_options(state: &mut SyncState, options: &str) {
+    if state.options != options {
+        for issue in state.issues.values_mut() {
+            issue.updated_at.clear();
+        }
+        state.options = options.to_string();
+    }
+}
+
+/// The update time to list issues since, or `None` when every issue has to
+/// be listed: on the first sync, after `--state` or the options changed, or
+/// when files are missing. Deleted and transferred issues never show up in
+/// a listing of updates, so only a full one removes their files.
+pub fn since<'a>(state: &'a SyncState, filter: &str, dir: &Path) -> Option<&'a str> {
+    let current = state.state == filter
+        && state
+            .issues
+            .values()
+            .all(|synced| !synced.updated_at.is_empty() && dir.join(&synced.file).exists());
+    state.last_update.as_deref().filter(|_| current)
+}
+
+/// Compares the listed issues with the last sync. An issue is fetched again
+/// when its `updated_at` changed, or its file is missing from `dir` or was
+/// written in another format, and removed when it no longer matches
+/// `filter`. With a `complete` listing, issues it leaves out are removed
+/// too; otherwise they are unchanged.
+pub fn plan(
+    state: &SyncState,
+    listed: Vec<ListedIssue>,
+    complete: bool,
+    filter: &str,
+    dir: &Path,
+    extension: &str,
+) -> Plan {
+    let mut plan = Plan::default();
+    let mut remaining = state.issues.clone();
+    let suffix = format!(".{}", extension);
+
+    for issue in listed {
+        let synced = remaining.remove(&issue.number);
+        if filter != "all" && issue.state != filter {
+            plan.remove.extend(synced.map(|synced| (issue.number, synced)));
+            continue;
+        }
+        let current = synced.is_some_and(|synced| {
+            synced.updated_at == issue.updated_at
+                && synced.file.ends_with(&suffix)
+                && dir.join(&synced.file).exists()
+        });
+        if current {
+            plan.unchanged += 1;
+        } else {
+            plan.fetch.push(issue);
+        }
+    }
+    if complete {
+        plan.remove.extend(remaining);
+    } else {
+        plan.unchanged += remaining.len();
+    }
+    plan
+}
+
+/// The newest update time among `listed` and the last sync, for the next
+/// sync to list issues since.
+pub fn last_update(state: &SyncState, listed: &[ListedIssue]) -> Option<String> {
+    listed
+        .iter()
+        .map(|issue| issue.updated_at.as_str())
+        .chain(state.last_update.as_deref())
+        .max()
+        .map(str::to_string)
+}
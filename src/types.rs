@@ -1,47 +1,259 @@
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
 
+/// Host of github.com, as opposed to a GitHub Enterprise Server instance.
+pub const DEFAULT_HOST: &str = "github.com";
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GhAuthor {
-    pub login: String,
+/// Whether a check run or job conclusion counts as failed.
+pub(crate) fn is_failing_conclusion(conclusion: Option<&str>) -> bool {
+    matches!(
+        conclusion,
+        Some("failure" | "timed_out" | "cancelled" | "action_required" | "startup_failure")
+    )
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GhComment {
-    pub author: Option<GhAuthor>,
-    pub body: String,
-    #[serde(rename = "createdAt")]
+#[derive(Debug, Serialize)]
+pub struct Metadata {
+    pub host: String, // "github.com" or a GitHub Enterprise Server hostname
+    pub repo: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub number: Option<u64>, // all but commits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha: Option<String>, // commits only
+    pub r#type: String, // "issue", "pr", "discussion" or "commit"
+    pub url: String,
+    pub author: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>, // OPEN, CLOSED or MERGED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_reason: Option<String>, // issues and discussions, e.g. COMPLETED, NOT_PLANNED, RESOLVED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub draft: Option<bool>, // PRs only
+    pub labels: Vec<String>,
+    pub assignees: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub milestone: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub closed_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_branch: Option<String>, // PRs only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub head_branch: Option<String>, // PRs only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>, // discussions only
+    pub locked: bool,
 }
 
-// Raw output from `gh issue view` or `gh pr view`
-#[derive(Debug, Deserialize)]
-pub struct GhResponse {
-    pub title: String,
+#[derive(Debug, Serialize)]
+pub struct UnifiedComment {
+    pub author: String,
     pub body: String,
-    pub url: String,
-    #[allow(dead_code)]
-    pub number: u64,
-    pub comments: Vec<GhComment>,
-    #[serde(default)]
-    pub author: Option<GhAuthor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    // Discussions only: the accepted answer, and replies to a top-level comment
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_answer: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub replies: Vec<UnifiedComment>,
 }
 
 #[derive(Debug, Serialize)]
-pub struct Metadata {
+pub struct TimelineEvent {
+    pub actor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+// Serialized with the REST event name in `event`, next to the kind's fields.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    Labeled {
+        label: String,
+    },
+    Unlabeled {
+        label: String,
+    },
+    Assigned {
+        assignee: String,
+    },
+    Unassigned {
+        assignee: String,
+    },
+    Renamed {
+        from: String,
+        to: String,
+    },
+    Closed {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state_reason: Option<String>,
+    },
+    Reopened,
+    Referenced {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+    },
+    #[serde(rename = "cross-referenced")]
+    CrossReferenced {
+        source: IssueReference,
+    },
+    Merged {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+    },
+    ReviewRequested {
+        reviewer: String,
+    },
+    ReviewRequestRemoved {
+        reviewer: String,
+    },
+    Milestoned {
+        milestone: String,
+    },
+    Demilestoned {
+        milestone: String,
+    },
+    Locked {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        lock_reason: Option<String>,
+    },
+    Unlocked,
+    ReadyForReview,
+    ConvertToDraft,
+    HeadRefForcePushed,
+    Commented,
+    Committed {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        commit: Option<String>,
+    },
+    Reviewed {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        state: Option<String>,
+    },
+    // Any other timeline event, by its REST name
+    #[serde(untagged)]
+    Other {
+        event: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct IssueReference {
     pub repo: String,
     pub number: u64,
-    pub r#type: String, // "issue" or "pr"
+    pub title: String,
     pub url: String,
-    pub author: String,
 }
 
 #[derive(Debug, Serialize)]
-pub struct UnifiedComment {
+pub struct Commit {
+    pub sha: String,
+    // Includes co-authors; GitHub login where known, otherwise the git name.
+    pub authors: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authored_at: Option<String>,
+    pub headline: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Review {
     pub author: String,
+    pub state: String, // APPROVED, CHANGES_REQUESTED, COMMENTED, DISMISSED
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<String>,
     pub body: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReviewThread {
+    pub path: String,
+    // Current position in the diff; None once the thread is outdated.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created_at: Option<String>,
+    pub line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_start_line: Option<u64>,
+    pub diff_hunk: String,
+    pub is_resolved: bool,
+    pub is_outdated: bool,
+    // The first comment starts the thread, the rest are replies.
+    pub comments: Vec<UnifiedComment>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Checks {
+    pub head_sha: String,
+    pub check_runs: Vec<CheckRun>,
+    pub statuses: Vec<CommitStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckRun {
+    pub id: u64,
+    pub name: String,
+    pub status: String, // queued, in_progress or completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conclusion: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    // Only fetched for failing runs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<CheckAnnotation>,
+    // Failing GitHub Actions jobs only, when log excerpts are requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_excerpt: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckAnnotation {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u64>,
+    pub level: String, // notice, warning or failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitStatus {
+    pub context: String,
+    pub state: String, // error, failure, pending or success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PullRequestLink {
+    pub number: u64,
+    pub title: String,
+    pub url: String,
+    pub state: String, // open, closed or merged
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub patch: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -50,5 +262,29 @@ pub struct Context {
     pub title: String,
     pub body: String,
     pub comments: Vec<UnifiedComment>,
-    pub events: Vec<serde_json::Value>,
+    pub events: Vec<TimelineEvent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commits: Option<Vec<Commit>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<Vec<Review>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_threads: Option<Vec<ReviewThread>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<Checks>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<Vec<FileDiff>>,
+    // Commits only: the PRs that contain the commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_requests: Option<Vec<PullRequestLink>>,
+    // Only filled when following references
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<Reference>,
+}
+
+// An issue or PR reached by following references from the requested item
+#[derive(Debug, Serialize)]
+pub struct Reference {
+    pub referenced_from: String, // owner/repo#number of the item that mentions it
+    pub depth: u32,
+    pub context: Context,
 }
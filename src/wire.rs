@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhAuthor {
+    pub login: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhComment {
+    pub author: Option<GhAuthor>,
+    pub body: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: Option<String>,
+    // Discussions only
+    #[serde(default, rename = "isAnswer")]
+    pub is_answer: bool,
+    #[serde(default)]
+    pub replies: Vec<GhComment>,
+}
+
+// Raw output from `gh issue view` or `gh pr view`
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhResponse {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+    #[allow(dead_code)]
+    pub number: u64,
+    pub comments: Vec<GhComment>,
+    #[serde(default)]
+    pub author: Option<GhAuthor>,
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub state_reason: Option<String>,
+    #[serde(default)]
+    pub is_draft: Option<bool>,
+    #[serde(default)]
+    pub labels: Vec<GhLabel>,
+    #[serde(default)]
+    pub assignees: Vec<GhAuthor>,
+    #[serde(default)]
+    pub milestone: Option<GhMilestone>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub closed_at: Option<String>,
+    #[serde(default)]
+    pub merged_at: Option<String>,
+    #[serde(default)]
+    pub base_ref_name: Option<String>,
+    #[serde(default)]
+    pub head_ref_name: Option<String>,
+    #[serde(default)]
+    pub head_ref_oid: Option<String>,
+    // Only requested for PRs
+    #[serde(default)]
+    pub reviews: Vec<GhReview>,
+    #[serde(default)]
+    pub commits: Vec<GhCommit>,
+    // Only requested for discussions
+    #[serde(default)]
+    pub category: Option<GhCategory>,
+    // Not returned by `gh issue view` or `gh pr view`
+    #[serde(default)]
+    pub locked: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhCategory {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhCommit {
+    pub oid: String,
+    #[serde(default)]
+    pub authors: Vec<GhCommitAuthor>,
+    pub authored_date: Option<String>,
+    pub message_headline: String,
+    #[serde(default)]
+    pub message_body: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhCommitAuthor {
+    #[serde(default)]
+    pub login: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhLabel {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GhMilestone {
+    pub title: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhReview {
+    pub author: Option<GhAuthor>,
+    pub state: String,
+    pub submitted_at: Option<String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhNodes<T> {
+    pub nodes: Vec<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhReviewComment {
+    pub author: Option<GhAuthor>,
+    pub body: String,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub diff_hunk: String,
+}
+
+// A node of `pullRequest.reviewThreads` from the GraphQL API
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GhReviewThread {
+    pub path: String,
+    pub line: Option<u64>,
+    pub start_line: Option<u64>,
+    pub original_line: Option<u64>,
+    pub original_start_line: Option<u64>,
+    pub is_resolved: bool,
+    pub is_outdated: bool,
+    pub comments: GhNodes<GhReviewComment>,
+}
+
+// Page of `GET /repos/{owner}/{repo}/commits/{ref}/check-runs`
+#[derive(Debug, Deserialize)]
+pub struct GhCheckRunsPage {
+    pub check_runs: Vec<GhCheckRun>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhCheckRun {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub html_url: Option<String>,
+    pub app: Option<GhApp>,
+    pub output: GhCheckRunOutput,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhApp {
+    pub slug: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhCheckRunOutput {
+    #[serde(default)]
+    pub annotations_count: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhCheckAnnotation {
+    pub path: String,
+    pub start_line: Option<u64>,
+    pub end_line: Option<u64>,
+    pub annotation_level: String,
+    pub title: Option<String>,
+    pub message: String,
+}
+
+// `GET /repos/{owner}/{repo}/commits/{ref}/status`
+#[derive(Debug, Deserialize)]
+pub struct GhCombinedStatus {
+    pub statuses: Vec<GhCommitStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhCommitStatus {
+    pub context: String,
+    pub state: String,
+    pub description: Option<String>,
+    pub target_url: Option<String>,
+}
+
+// An item of `GET /repos/{owner}/{repo}/issues/{number}/timeline`. The
+// GraphQL path rewrites its timeline nodes into this shape as well.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct GhTimelineEvent {
+    pub event: String,
+    pub actor: Option<GhAuthor>,
+    pub created_at: Option<String>,
+    pub label: Option<GhLabel>,
+    pub assignee: Option<GhAuthor>,
+    pub rename: Option<GhRename>,
+    pub commit_id: Option<String>,
+    pub state_reason: Option<String>,
+    pub source: Option<GhEventSource>,
+    pub milestone: Option<GhMilestone>,
+    pub lock_reason: Option<String>,
+    pub requested_reviewer: Option<GhAuthor>,
+    pub requested_team: Option<GhTeam>,
+    // `commented` and `reviewed` name the user instead of an actor, and
+    // `reviewed` has `submitted_at` instead of `created_at`.
+    pub user: Option<GhAuthor>,
+    pub submitted_at: Option<String>,
+    pub state: Option<String>,
+    // `committed` has neither; only the git author and committer.
+    pub sha: Option<String>,
+    pub author: Option<GhGitActor>,
+    pub committer: Option<GhGitActor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhRename {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhEventSource {
+    pub issue: Option<GhSourceIssue>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhSourceIssue {
+    pub number: u64,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub html_url: String,
+    pub repository: Option<GhRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhRepository {
+    pub full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhTeam {
+    pub name: String,
+}
+
+// `GET /repos/{owner}/{repo}/commits/{sha}`
+#[derive(Debug, Deserialize)]
+pub struct GhCommitDetail {
+    pub sha: String,
+    pub html_url: String,
+    pub commit: GhGitCommit,
+    pub author: Option<GhAuthor>, // the linked GitHub account, if any
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhGitCommit {
+    pub message: String,
+    pub author: Option<GhGitActor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GhGitActor {
+    pub name: String,
+    pub date: Option<String>,
+}
+
+// `GET /repos/{owner}/{repo}/commits/{sha}/comments`
+#[derive(Debug, Deserialize)]
+pub struct GhCommitComment {
+    pub user: Option<GhAuthor>,
+    #[serde(default)]
+    pub body: String,
+    pub created_at: Option<String>,
+    pub path: Option<String>,
+    pub line: Option<u64>,
+}
+
+// `GET /repos/{owner}/{repo}/commits/{sha}/pulls`
+#[derive(Debug, Deserialize)]
+pub struct GhPullSummary {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    pub state: String,
+    pub merged_at: Option<String>,
+}